@@ -1,254 +1,1636 @@
-use rand::seq::SliceRandom;
-use rand::thread_rng;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::ops::RangeInclusive;
+use std::str::FromStr;
 
-use crate::card::Card;
-use crate::game_common::Game;
+use rand::Rng;
+
+use crate::card::{Card, Rank};
+use crate::controller::{Action, BetView, ControllerKind, InsuranceView, TurnView};
+use crate::counting::{Count, Counter, CountingKind, CountingSystem};
+use crate::deck::Shoe;
+use crate::error::CardsError;
+use crate::game_common::{check_player_count, leaders, Game, RoundOutcome, Standing};
 use crate::player::Player;
-use crate::ui::*;
+use crate::strategy::Strategy;
+use crate::ui::{Seat, Table, UserInterface};
 
-pub struct BlackJack {
-    players: Vec<Player>,
-    busted_players: Vec<Player>,
-    deck: Vec<Card>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HandValue {
+    pub total: i32,
+    // an ace is counted as 11 and can still drop to 1
+    pub soft: bool,
 }
 
-impl BlackJack {
-    pub fn new(player_names: Vec<String>) -> BlackJack {
-        assert!(player_names.len() > 1, "E_NOT_ENOUGH_PLAYERS");
-        assert!(player_names.len() < 5, "E_TOO_MANY_PLAYERS");
+impl Display for HandValue {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.soft {
+            write!(f, "soft {}", self.total)
+        } else {
+            write!(f, "{}", self.total)
+        }
+    }
+}
 
-        let mut players = Vec::new();
-        let mut deck = Card::new_deck_blackjack();
-        let busted_players = Vec::new();
+// the best total for the hand, aces count as 11 unless that would bust
+pub fn hand_value(cards: &[Card]) -> HandValue {
+    let hard = cards
+        .iter()
+        .map(|x| match x.rank {
+            Rank::Ace => 1,
+            rank => rank.blackjack_value(),
+        })
+        .sum::<i32>();
 
-        for (i, name) in player_names.iter().enumerate() {
-            let mut player = Player::new(name.to_string(), i as i32, &mut deck, 2);
-            let hand_total: i32 = player
-                .deck
-                .iter()
-                .map(|x| x.blackjack_cmp_val().unwrap())
-                .sum();
-
-            for card in player.deck.iter_mut() {
-                if card.value_id == 14 {
-                    card.value_id = match BlackJack::redef_ace(name.to_string(), hand_total - 11) {
-                        1 => 1,
-                        11 => 14,
-                        _ => panic!("E_INVALID_VALUE"),
-                    };
-
-                    card.refresh_blackjack().unwrap();
-                }
+    if cards.iter().any(|x| x.rank == Rank::Ace) && hard + 10 <= 21 {
+        HandValue {
+            total: hard + 10,
+            soft: true,
+        }
+    } else {
+        HandValue {
+            total: hard,
+            soft: false,
+        }
+    }
+}
+
+// two card 21
+pub fn is_natural(cards: &[Card]) -> bool {
+    cards.len() == 2 && hand_value(cards).total == 21
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Payout {
+    ThreeToTwo,
+    SixToFive,
+}
+
+impl Payout {
+    // winnings are rounded down to whole chips
+    pub fn pay(self, bet: i32) -> i32 {
+        match self {
+            Payout::ThreeToTwo => bet * 3 / 2,
+            Payout::SixToFive => bet * 6 / 5,
+        }
+    }
+}
+
+impl Display for Payout {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Payout::ThreeToTwo => write!(f, "3:2"),
+            Payout::SixToFive => write!(f, "6:5"),
+        }
+    }
+}
+
+impl FromStr for Payout {
+    type Err = CardsError;
+
+    fn from_str(s: &str) -> Result<Payout, CardsError> {
+        match s.trim() {
+            "3:2" => Ok(Payout::ThreeToTwo),
+            "6:5" => Ok(Payout::SixToFive),
+            _ => Err(CardsError::InvalidInput(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BlackJackRules {
+    // without a dealer the players compete against each other
+    pub dealer: bool,
+    pub dealer_hits_soft_17: bool,
+    // house rule: players pick 1 or 11 for every ace they get and keep it
+    pub manual_aces: bool,
+    // betting only happens against the dealer
+    pub starting_bankroll: i32,
+    pub table_min: i32,
+    pub table_max: i32,
+    pub blackjack_pays: Payout,
+    // totals a player may double down on, 9..=11 is a common restriction
+    pub double_totals: RangeInclusive<i32>,
+    pub double_after_split: bool,
+    // most hands one player can split into
+    pub max_hands: usize,
+    pub resplit_aces: bool,
+    // split aces normally get a single card each
+    pub hit_split_aces: bool,
+    // late surrender, after the dealer checked for blackjack
+    pub surrender: bool,
+    pub insurance: bool,
+    // the dealer checks the hole card for blackjack when showing an ace or ten
+    pub dealer_peeks: bool,
+    pub decks: usize,
+    // share of the shoe dealt before it is reshuffled between hands
+    pub penetration: f64,
+    // human players can ask for the basic strategy play
+    pub hints: bool,
+    // card counting practice for human players
+    pub counting: Option<CountingKind>,
+    // hands between count quizzes, 0 for none
+    pub quiz_every: u32,
+}
+
+impl Default for BlackJackRules {
+    fn default() -> BlackJackRules {
+        BlackJackRules {
+            dealer: true,
+            dealer_hits_soft_17: false,
+            manual_aces: false,
+            starting_bankroll: 1000,
+            table_min: 10,
+            table_max: 500,
+            blackjack_pays: Payout::ThreeToTwo,
+            double_totals: 2..=21,
+            double_after_split: true,
+            max_hands: 4,
+            resplit_aces: false,
+            hit_split_aces: false,
+            surrender: true,
+            insurance: true,
+            dealer_peeks: true,
+            decks: 6,
+            penetration: 0.75,
+            hints: true,
+            counting: None,
+            quiz_every: 5,
+        }
+    }
+}
+
+impl BlackJackRules {
+    // the rules that matter most for the house edge, like "6 decks, S17, DAS, 3:2"
+    pub fn summary(&self) -> String {
+        let mut rules = vec![
+            format!("{} decks", self.decks),
+            if self.dealer_hits_soft_17 {
+                "H17"
+            } else {
+                "S17"
             }
-            player.deck[0].visible = false;
+            .to_string(),
+        ];
+
+        if self.double_after_split {
+            rules.push("DAS".to_string());
+        }
+
+        if self.surrender {
+            rules.push("LS".to_string());
+        }
+
+        if !self.dealer_peeks {
+            rules.push("no peek".to_string());
+        }
+
+        rules.push(self.blackjack_pays.to_string());
+        rules.join(", ")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Win,
+    // a natural against a dealer without one
+    Blackjack,
+    Lose,
+    Push,
+    // half the bet back
+    Surrender,
+}
+
+impl Outcome {
+    pub fn against(player: HandValue, dealer: HandValue) -> Outcome {
+        if player.total > 21 {
+            Outcome::Lose
+        } else if dealer.total > 21 || player.total > dealer.total {
+            Outcome::Win
+        } else if player.total < dealer.total {
+            Outcome::Lose
+        } else {
+            Outcome::Push
+        }
+    }
+
+    // chips handed back to the player, the bet included
+    pub fn payout(self, bet: i32, blackjack_pays: Payout) -> i32 {
+        match self {
+            Outcome::Win => bet * 2,
+            Outcome::Blackjack => bet + blackjack_pays.pay(bet),
+            Outcome::Lose => 0,
+            Outcome::Push => bet,
+            Outcome::Surrender => bet / 2,
+        }
+    }
+}
 
+#[derive(Clone, Debug, Default)]
+pub struct Hand {
+    pub cards: Vec<Card>,
+    pub bet: i32,
+    // 21 on the first two cards of a split hand is not a natural
+    pub split: bool,
+    pub doubled: bool,
+    // stood, busted, doubled or surrendered
+    pub done: bool,
+    // set once the hand is settled and paid
+    pub outcome: Option<Outcome>,
+    // aces counted as 1 with the manual aces house rule
+    low_aces: i32,
+}
+
+impl Hand {
+    pub fn new(bet: i32) -> Hand {
+        Hand {
+            bet,
+            ..Hand::default()
+        }
+    }
+
+    pub fn is_natural(&self) -> bool {
+        !self.split && is_natural(&self.cards)
+    }
+
+    // with manual aces, aces count as 11 unless the player picked 1 for them
+    pub fn value(&self, manual_aces: bool) -> HandValue {
+        if !manual_aces {
+            return hand_value(&self.cards);
+        }
+
+        HandValue {
+            total: self
+                .cards
+                .iter()
+                .map(|x| x.rank.blackjack_value())
+                .sum::<i32>()
+                - self.low_aces * 10,
+            soft: false,
+        }
+    }
+
+    pub fn is_pair(&self) -> bool {
+        self.cards.len() == 2
+            && self.cards[0].rank.blackjack_value() == self.cards[1].rank.blackjack_value()
+    }
+}
+
+// the cards of a finished hand, kept until the next one ends
+#[derive(Clone, Debug, Default)]
+pub struct HandRecord {
+    pub hands: HashMap<i32, Vec<Hand>>,
+    pub dealer: Vec<Card>,
+}
+
+pub struct BlackJack<U: UserInterface> {
+    players: Vec<Player>,
+    // cashed out or broke
+    left_players: Vec<Player>,
+    dealer: Option<Player>,
+    hands: HashMap<i32, Vec<Hand>>,
+    insurance: HashMap<i32, i32>,
+    shoe: Shoe,
+    hands_played: u32,
+    strategy: Strategy,
+    counter: Option<Counter>,
+    last_hand: HandRecord,
+    pub rules: BlackJackRules,
+    pub ui: U,
+}
+
+impl<U: UserInterface> BlackJack<U> {
+    pub fn new(
+        player_names: Vec<String>,
+        rng: &mut impl Rng,
+        ui: U,
+    ) -> Result<BlackJack<U>, CardsError> {
+        let players = player_names
+            .into_iter()
+            .map(|x| (x, ControllerKind::Human))
+            .collect();
+
+        BlackJack::with_controllers(players, rng, ui)
+    }
+
+    pub fn with_controllers(
+        seats: Vec<(String, ControllerKind)>,
+        rng: &mut impl Rng,
+        ui: U,
+    ) -> Result<BlackJack<U>, CardsError> {
+        BlackJack::with_rules(seats, BlackJackRules::default(), rng, ui)
+    }
+
+    pub fn with_rules(
+        seats: Vec<(String, ControllerKind)>,
+        rules: BlackJackRules,
+        rng: &mut impl Rng,
+        ui: U,
+    ) -> Result<BlackJack<U>, CardsError> {
+        let min = if rules.dealer { 1 } else { 2 };
+        check_player_count(seats.len(), min, 4)?;
+
+        if rules.table_min < 1 || rules.table_max < rules.table_min {
+            return Err(CardsError::InvalidArgument(format!(
+                "the table limits {} to {} are not valid",
+                rules.table_min, rules.table_max
+            )));
+        }
+
+        if rules.dealer && rules.starting_bankroll < rules.table_min {
+            return Err(CardsError::InvalidArgument(format!(
+                "a bankroll of {} can't cover the table minimum of {}",
+                rules.starting_bankroll, rules.table_min
+            )));
+        }
+
+        if rules.max_hands < 1 {
+            return Err(CardsError::InvalidArgument(
+                "players need at least one hand".to_string(),
+            ));
+        }
+
+        if rules.decks < 1 {
+            return Err(CardsError::InvalidArgument(
+                "the shoe needs at least one deck".to_string(),
+            ));
+        }
+
+        let mut players = Vec::new();
+        let mut shoe = Shoe::shoe(rules.decks, rng);
+        shoe.set_penetration(rules.penetration)?;
+        shoe.shuffle();
+
+        for (i, (name, controller)) in seats.into_iter().enumerate() {
+            let mut player = Player::new(name, i as i32, &mut shoe, 0)?;
+            player.controller = controller;
+            player.bankroll = rules.starting_bankroll;
             players.push(player);
         }
 
-        BlackJack {
+        let dealer = if rules.dealer {
+            let mut dealer = Player::new("Dealer".to_string(), -1, &mut shoe, 0)?;
+            dealer.controller = ControllerKind::HitBelow(17);
+            Some(dealer)
+        } else {
+            None
+        };
+
+        Ok(BlackJack {
             players,
-            deck,
-            busted_players,
+            left_players: Vec::new(),
+            dealer,
+            hands: HashMap::new(),
+            insurance: HashMap::new(),
+            shoe,
+            hands_played: 0,
+            strategy: Strategy::new(&rules),
+            counter: rules.counting.map(|x| Counter::new(x, rules.decks)),
+            last_hand: HandRecord::default(),
+            rules,
+            ui,
+        })
+    }
+
+    pub fn hands(&self, player_id: i32) -> &[Hand] {
+        self.hands.get(&player_id).map_or(&[], Vec::as_slice)
+    }
+
+    // turns counting practice on with any system, replacing rules.counting
+    pub fn set_counting_system(&mut self, system: Box<dyn CountingSystem>) {
+        let mut counter = Counter::with_system(system, self.rules.decks);
+        counter.see_all(self.shoe.discards());
+        self.counter = Some(counter);
+    }
+
+    pub fn last_hand(&self) -> &HandRecord {
+        &self.last_hand
+    }
+
+    // the count including the face-up cards on the table, none unless
+    // counting is on
+    pub fn count(&self) -> Option<Count> {
+        let counter = self.counter.as_ref()?;
+        let on_table = self
+            .hands
+            .values()
+            .flatten()
+            .flat_map(|x| x.cards.iter())
+            .chain(self.dealer.iter().flat_map(|x| x.deck.iter()))
+            .filter(|x| x.visible)
+            .map(|x| counter.tag(x))
+            .sum::<i32>();
+
+        Some(counter.count(counter.running() + on_table, self.shoe.remaining()))
+    }
+
+    fn quiz(&mut self) -> Result<(), CardsError> {
+        let has_humans = self.players.iter().any(|x| x.controller.is_human());
+
+        let (Some(count), Some(counter)) = (self.count(), self.counter.as_mut()) else {
+            return Ok(());
+        };
+
+        if !has_humans
+            || self.rules.quiz_every == 0
+            || self.hands_played == 0
+            || !self.hands_played.is_multiple_of(self.rules.quiz_every)
+        {
+            return Ok(());
         }
+
+        let answer = self.ui.ask_number(
+            &format!("Count check! What is the {} running count? ", count.system),
+            -1000..=1000,
+            None,
+        )?;
+        counter.record_answer(answer == count.running);
+
+        if answer == count.running {
+            self.ui.show_message(&format!("Correct! {}", count));
+        } else {
+            self.ui.show_message(&format!("Not quite. {}", count));
+        }
+
+        let (correct, asked) = counter.accuracy();
+        self.ui.show_message(&format!(
+            "Count accuracy: {} of {} ({}%)\n",
+            correct,
+            asked,
+            correct * 100 / asked
+        ));
+
+        Ok(())
     }
 
-    fn print_player_cards(&self, player_id: i32) {
-        for player in &self.players {
-            print!("{} has: ", player.name);
-
-            // print player.deck[i].value with proper comma and "and" logic
-            for (i, card) in player.deck.iter().enumerate() {
-                let mut card_value = if card.visible || player.id == player_id {
-                    match card
-                        .value
-                        .chars()
-                        .next()
-                        .unwrap()
-                        .to_lowercase()
-                        .to_string()
-                        .as_str()
-                    {
-                        "a" | "e" | "i" | "o" | "u" => format!("an {}", card.value),
-                        _ => format!("a {}", card.value),
-                    }
-                } else {
-                    "a face-down card".to_string()
-                };
+    fn hand_mut(&mut self, player_id: i32, hand: usize) -> Result<&mut Hand, CardsError> {
+        self.hands
+            .get_mut(&player_id)
+            .and_then(|x| x.get_mut(hand))
+            .ok_or(CardsError::UnknownPlayer(player_id))
+    }
 
-                // if i is 0, make the first letter in card_value uppercase, then set it to card_value
-                if i == 0 {
-                    card_value = card_value.remove(0).to_uppercase().to_string() + &card_value;
-                }
+    fn player_mut(&mut self, player_id: i32) -> Result<&mut Player, CardsError> {
+        self.players
+            .iter_mut()
+            .find(|x| x.id == player_id)
+            .ok_or(CardsError::UnknownPlayer(player_id))
+    }
+
+    // "alice", or "alice (hand 2)" once she split
+    fn hand_name(&self, player: &Player, hand: usize) -> String {
+        if self.hands(player.id).len() > 1 {
+            format!("{} (hand {})", player.name, hand + 1)
+        } else {
+            player.name.clone()
+        }
+    }
+
+    fn betting(&mut self) -> Result<(), CardsError> {
+        self.ui.clear();
+        self.quiz()?;
+
+        if self.shoe.needs_shuffle() {
+            self.reshuffle();
+        }
+
+        self.ui.show_message(&format!(
+            "Place your bets, the table takes {} to {} chips.",
+            self.rules.table_min, self.rules.table_max
+        ));
+
+        let mut index = 0;
 
-                if i == player.deck.len() - 1 {
-                    println!("and {}", card_value);
-                } else {
-                    print!("{}, ", card_value);
+        while index < self.players.len() {
+            let player = &self.players[index];
+            let max = self.rules.table_max.min(player.bankroll);
+            let view = BetView {
+                name: &player.name,
+                bankroll: player.bankroll,
+                min: self.rules.table_min,
+                max,
+            };
+
+            match player.controller.controller().bet(&mut self.ui, &view)? {
+                Some(bet) => {
+                    // players below the minimum left the table after the last hand
+                    let bet = bet.max(self.rules.table_min).min(max);
+                    let player = &mut self.players[index];
+                    player.bankroll -= bet;
+                    self.hands.insert(player.id, vec![Hand::new(bet)]);
+
+                    let message = format!("{} bets {}", player.name, bet);
+                    self.ui.show_message(&message);
+                    index += 1;
+                }
+                None => {
+                    let player = self.players.remove(index);
+                    self.ui.show_message(&format!(
+                        "{} cashes out with {} chips",
+                        player.name, player.bankroll
+                    ));
+                    self.left_players.push(player);
                 }
             }
         }
 
-        for player in &self.busted_players {
-            println!("{}: busted with a total of {}", player.name, BlackJack::get_hand_total(&player.deck));
+        self.ui.pause()
+    }
+
+    // the cards on the table are not in the discards yet, so they are still
+    // counted once the hand is over
+    fn reshuffle(&mut self) {
+        self.shoe.reshuffle();
+        self.ui.show_message("The dealer reshuffles the shoe.");
+
+        if let Some(counter) = &mut self.counter {
+            counter.reset();
+        }
+    }
+
+    // a shoe dealt down to the last card is reshuffled in the middle of a hand
+    fn deal_card(&mut self) -> Result<Card, CardsError> {
+        if self.shoe.remaining() == 0 {
+            self.reshuffle();
         }
+
+        self.shoe.deal()
     }
 
-    pub fn redef_ace(player_name: String, hand_total: i32) -> i32 {
-        let value = prompt!("{}, you have an ace. Your current hand total is {}. What do you want it to be worth (1 or 11)? ",  player_name, hand_total).trim().parse().unwrap();
+    fn deal(&mut self) -> Result<(), CardsError> {
+        for index in 0..self.players.len() {
+            let player_id = self.players[index].id;
+            self.hands
+                .entry(player_id)
+                .or_insert_with(|| vec![Hand::new(0)]);
+
+            for _ in 0..2 {
+                self.draw(player_id, 0)?;
+            }
+
+            // against the dealer everything is dealt face up
+            if self.dealer.is_none() {
+                self.hand_mut(player_id, 0)?.cards[0].visible = false;
+            }
+        }
+
+        if self.dealer.is_some() {
+            let cards = vec![self.deal_card()?, self.deal_card()?];
 
-        assert!(value == 1 || value == 11, "E_INVALID_VALUE");
-        value
+            if let Some(dealer) = &mut self.dealer {
+                dealer.deck = cards;
+                dealer.deck[1].visible = false;
+            }
+        }
+
+        Ok(())
     }
 
-    fn handle_hit(&mut self, player_id: i32) -> std::option::Option<Card> {
-        let players = &mut self.players;
-        let player = players.iter().find(|p| p.id == player_id).unwrap();
-        let mut hand_total: i32 = BlackJack::get_hand_total(&player.deck);
+    // deals a card onto the hand, asking for the ace's value with manual aces
+    fn draw(&mut self, player_id: i32, hand: usize) -> Result<Card, CardsError> {
+        let card = self.deal_card()?;
+
+        if self.rules.manual_aces && card.rank == Rank::Ace {
+            let player = self
+                .players
+                .iter()
+                .find(|x| x.id == player_id)
+                .ok_or(CardsError::UnknownPlayer(player_id))?;
+            let total = self.hands(player_id)[hand]
+                .value(self.rules.manual_aces)
+                .total;
 
-        let mut card = match self.deck.pop() {
-            Some(x) => x,
-            None => {
-                self.deck = Card::new_deck();
-                self.deck.shuffle(&mut thread_rng());
-                self.deck.pop().unwrap()
+            if player
+                .controller
+                .controller()
+                .ace_value(&mut self.ui, &player.name, total)?
+                == 1
+            {
+                self.hand_mut(player_id, hand)?.low_aces += 1;
             }
+        }
+
+        self.hand_mut(player_id, hand)?.cards.push(card);
+
+        Ok(card)
+    }
+
+    fn offer_insurance(&mut self) -> Result<(), CardsError> {
+        let Some(dealer) = &self.dealer else {
+            return Ok(());
         };
 
-        let card_value = if card.value_id == 14 {
-            BlackJack::redef_ace(player.name.to_string(), hand_total)
+        if !self.rules.insurance || dealer.deck[0].rank != Rank::Ace {
+            return Ok(());
+        }
+
+        self.ui.clear();
+        self.print_player_cards(None);
+        self.ui.show_message("\nThe dealer shows an ace.");
+
+        for index in 0..self.players.len() {
+            let player = &self.players[index];
+            let hand = &self.hands(player.id)[0];
+            let cost = hand.bet / 2;
+            let natural = hand.is_natural();
+
+            if !natural && (cost == 0 || player.bankroll < cost) {
+                continue;
+            }
+
+            let view = InsuranceView {
+                name: &player.name,
+                bet: hand.bet,
+                natural,
+            };
+
+            if !player
+                .controller
+                .controller()
+                .insurance(&mut self.ui, &view)?
+            {
+                continue;
+            }
+
+            let player_id = player.id;
+
+            if natural {
+                self.settle(player_id, 0, Outcome::Win)?;
+            } else {
+                self.players[index].bankroll -= cost;
+                self.insurance.insert(player_id, cost);
+
+                let message = format!("{} takes insurance for {}", self.players[index].name, cost);
+                self.ui.show_message(&message);
+            }
+        }
+
+        self.ui.pause()
+    }
+
+    // the viewer sees their own face-down card, computer players show nothing
+    fn print_player_cards(&mut self, viewer: Option<i32>) {
+        let mut seats = Vec::new();
+
+        if let Some(dealer) = &self.dealer {
+            self.ui.show_hand("Dealer has", &dealer.deck);
+
+            // the total is only known once the hole card is turned over
+            let detail = if dealer.deck.iter().all(|x| x.visible) {
+                format!("total {}", hand_value(&dealer.deck))
+            } else {
+                String::new()
+            };
+
+            seats.push(Seat {
+                name: dealer.name.clone(),
+                cards: dealer.deck.clone(),
+                detail,
+            });
+        }
+
+        let mut hands = Vec::new();
+
+        for player in &self.players {
+            for (i, hand) in self.hands(player.id).iter().enumerate() {
+                hands.push((player.id, self.hand_name(player, i), hand.clone()));
+            }
+        }
+
+        for (player_id, name, hand) in hands {
+            let value = hand.value(self.rules.manual_aces);
+            let busted = value.total > 21;
+
+            let cards = hand
+                .cards
+                .iter()
+                .map(|card| Card {
+                    visible: card.visible || busted || Some(player_id) == viewer,
+                    ..*card
+                })
+                .collect::<Vec<Card>>();
+
+            self.ui.show_hand(&format!("{} has", name), &cards);
+
+            let mut detail = Vec::new();
+
+            if busted {
+                self.ui
+                    .show_message(&format!("{}: busted with a total of {}", name, value));
+                detail.push(format!("busted with {}", value));
+            } else if Some(player_id) == viewer {
+                detail.push(format!("total {}", value));
+            }
+
+            if hand.outcome == Some(Outcome::Surrender) {
+                detail.push("surrendered".to_string());
+            }
+
+            if hand.doubled {
+                detail.push(format!("doubled to {}", hand.bet));
+            } else if hand.bet > 0 {
+                detail.push(format!("bet {}", hand.bet));
+            }
+
+            seats.push(Seat {
+                name,
+                cards,
+                detail: detail.join(", "),
+            });
+        }
+
+        self.ui.show_table(&Table {
+            seats,
+            floor: Vec::new(),
+        });
+    }
+
+    // what the rules allow for a hand, only hit and stand without a dealer
+    fn actions(&self, player: &Player, hand: usize) -> Vec<Action> {
+        if self.dealer.is_none() {
+            return vec![Action::Hit, Action::Stand];
+        }
+
+        let hands = self.hands(player.id);
+        let hand = &hands[hand];
+        let first = hand.cards.len() == 2;
+        let affordable = player.bankroll >= hand.bet;
+        let split_aces = hand.split && hand.cards[0].rank == Rank::Ace;
+
+        // split aces that can't be hit only get to split again
+        let mut actions = if split_aces && !self.rules.hit_split_aces {
+            vec![Action::Stand]
         } else {
-            BlackJack::get_hand_total(&[card.clone()])
+            vec![Action::Hit, Action::Stand]
         };
-        card.value_id = card_value;
-        card.refresh_blackjack().unwrap();
 
-        hand_total += card_value;
+        if first
+            && affordable
+            && actions.contains(&Action::Hit)
+            && self
+                .rules
+                .double_totals
+                .contains(&hand.value(self.rules.manual_aces).total)
+            && (!hand.split || self.rules.double_after_split)
+        {
+            actions.push(Action::Double);
+        }
 
-        if hand_total > 21 {
-            println!(
-                "You busted with a {} and a total of {}",
-                card.value, hand_total
+        if hand.is_pair()
+            && affordable
+            && hands.len() < self.rules.max_hands
+            && (!split_aces || self.rules.resplit_aces)
+        {
+            actions.push(Action::Split);
+        }
+
+        // without a peek a dealer blackjack is still possible against an ace or
+        // ten, and late surrender has to wait for it
+        let unpeeked = !self.rules.dealer_peeks
+            && self
+                .dealer
+                .as_ref()
+                .is_some_and(|x| matches!(x.deck[0].rank.blackjack_value(), 10 | 11));
+
+        if first && self.rules.surrender && hands.len() == 1 && !unpeeked {
+            actions.push(Action::Surrender);
+        }
+
+        actions
+    }
+
+    // false when the hand busted
+    fn handle_hit(&mut self, player_id: i32, hand: usize) -> Result<bool, CardsError> {
+        let card = self.draw(player_id, hand)?;
+
+        let player = self
+            .players
+            .iter()
+            .find(|x| x.id == player_id)
+            .ok_or(CardsError::UnknownPlayer(player_id))?;
+        let name = self.hand_name(player, hand);
+        let value = self.hands(player_id)[hand].value(self.rules.manual_aces);
+
+        if value.total > 21 {
+            let message = format!(
+                "{} busted with {} and a total of {}",
+                name,
+                self.ui.card_name(&card),
+                value
             );
-            let mut player = players.remove(Player::player_index(players, player.id).unwrap());
-            player.deck.push(card);
-            self.busted_players.push(player);
+            self.ui.show_message(&message);
+            self.hand_mut(player_id, hand)?.done = true;
 
-            None
+            Ok(false)
         } else {
-            println!("Your hand total is now {}", hand_total);
-            Some(card)
+            let message = format!(
+                "{} drew {}, their hand total is now {}",
+                name,
+                self.ui.card_name(&card),
+                value
+            );
+            self.ui.show_message(&message);
+
+            Ok(true)
+        }
+    }
+
+    fn double(&mut self, player_id: i32, hand: usize) -> Result<(), CardsError> {
+        let bet = self.hands(player_id)[hand].bet;
+        let player = self.player_mut(player_id)?;
+        player.bankroll -= bet;
+        let message = format!("{} doubles down to {}", player.name, bet * 2);
+
+        let doubled = self.hand_mut(player_id, hand)?;
+        doubled.bet += bet;
+        doubled.doubled = true;
+
+        self.ui.show_message(&message);
+
+        // one card and no more
+        self.handle_hit(player_id, hand)?;
+        self.hand_mut(player_id, hand)?.done = true;
+
+        Ok(())
+    }
+
+    fn split(&mut self, player_id: i32, hand: usize) -> Result<(), CardsError> {
+        let bet = self.hands(player_id)[hand].bet;
+        let player = self.player_mut(player_id)?;
+        player.bankroll -= bet;
+        let name = player.name.clone();
+
+        let first = self.hand_mut(player_id, hand)?;
+        let card = first.cards.pop().ok_or(CardsError::DeckExhausted)?;
+        first.split = true;
+        first.low_aces = 0;
+
+        let hands = self
+            .hands
+            .get_mut(&player_id)
+            .ok_or(CardsError::UnknownPlayer(player_id))?;
+        hands.insert(
+            hand + 1,
+            Hand {
+                cards: vec![card],
+                bet,
+                split: true,
+                ..Hand::default()
+            },
+        );
+
+        let message = format!("{} splits a pair of {}s", name, card.rank.name());
+        self.ui.show_message(&message);
+
+        // both hands get their second card right away
+        for i in [hand, hand + 1] {
+            let card = self.draw(player_id, i)?;
+            let player = self
+                .players
+                .iter()
+                .find(|x| x.id == player_id)
+                .ok_or(CardsError::UnknownPlayer(player_id))?;
+
+            let message = format!(
+                "{} gets {}, their hand total is {}",
+                self.hand_name(player, i),
+                self.ui.card_name(&card),
+                self.hands(player_id)[i].value(self.rules.manual_aces)
+            );
+            self.ui.show_message(&message);
+        }
+
+        if card.rank == Rank::Ace && !self.rules.hit_split_aces {
+            self.ui.show_message("Split aces get one card each.");
         }
+
+        if !self.rules.hit_split_aces {
+            let index = Player::player_index(&self.players, player_id)
+                .ok_or(CardsError::UnknownPlayer(player_id))?;
+
+            // a pair of split aces stays open while it can be split again,
+            // which may have just stopped with this split
+            for i in 0..self.hands(player_id).len() {
+                let open = self
+                    .actions(&self.players[index], i)
+                    .contains(&Action::Split);
+                let aces = self.hand_mut(player_id, i)?;
+
+                if aces.split && aces.cards[0].rank == Rank::Ace && !open {
+                    aces.done = true;
+                }
+            }
+        }
+
+        Ok(())
     }
 
-    fn turn(&mut self, player_id: i32) -> bool {
-        self.print_player_cards(player_id);
+    // true when the hand is finished
+    fn turn(&mut self, player_id: i32, hand: usize) -> Result<bool, CardsError> {
+        let player_index = Player::player_index(&self.players, player_id)
+            .ok_or(CardsError::UnknownPlayer(player_id))?;
+        let mut controller = self.players[player_index].controller.controller();
+
+        if controller.is_human() {
+            self.print_player_cards(Some(player_id));
+        }
 
-        let player_index = Player::player_index(&self.players, player_id).expect("E_UNKNOWN");
         let player = &self.players[player_index];
+        let actions = self.actions(player, hand);
+        let hands = self.hands.get(&player_id).map_or(&[][..], Vec::as_slice);
+        let value = hands[hand].value(self.rules.manual_aces);
+        let upcard = self.dealer.as_ref().map(|x| x.deck[0]);
 
-        println!();
-        let choice = prompt_options!(
-            format!(
-                "{}'s turn. Your total is {}. What do you want to do?",
-                player.name,
-                BlackJack::get_hand_total(&player.deck)
-            ),
-            &[
-                Option {
-                    name: "Hit",
-                    value: 1,
-                },
-                Option {
-                    name: "Stand",
-                    value: 2,
-                },
-            ]
+        // the charts are for playing against a dealer
+        let hint = upcard
+            .filter(|_| self.rules.hints || !controller.is_human())
+            .map(|x| self.strategy.recommend(&hands[hand].cards, x, &actions));
+        let count = self.count().filter(|_| controller.is_human());
+
+        let view = TurnView {
+            name: &player.name,
+            cards: &hands[hand].cards,
+            total: value.total,
+            soft: value.soft,
+            upcard,
+            actions: &actions,
+            hint,
+            count,
+            hand: hand + 1,
+            hands: hands.len(),
+        };
+        let action = controller.action(&mut self.ui, &view)?;
+
+        if !actions.contains(&action) {
+            return Err(CardsError::InvalidInput(action.name().to_string()));
+        }
+
+        if controller.is_human() {
+            self.ui.clear();
+        }
+
+        match action {
+            Action::Hit => {
+                self.handle_hit(player_id, hand)?;
+            }
+            Action::Stand => {
+                let message = format!(
+                    "{} stood.",
+                    self.hand_name(&self.players[player_index], hand)
+                );
+                self.ui.show_message(&message);
+                self.hand_mut(player_id, hand)?.done = true;
+            }
+            Action::Double => self.double(player_id, hand)?,
+            Action::Split => self.split(player_id, hand)?,
+            Action::Surrender => self.settle(player_id, hand, Outcome::Surrender)?,
+        }
+
+        Ok(self.hands(player_id)[hand].done)
+    }
+
+    fn dealer_turn(&mut self) -> Result<(), CardsError> {
+        // nobody is left to beat when every hand busted or was settled
+        let live = self
+            .hands
+            .values()
+            .flatten()
+            .any(|x| x.outcome.is_none() && x.value(self.rules.manual_aces).total <= 21);
+
+        let Some(mut dealer) = self.dealer.take() else {
+            return Ok(());
+        };
+
+        self.ui.clear();
+
+        for card in dealer.deck.iter_mut() {
+            card.visible = true;
+        }
+
+        let message = format!(
+            "The dealer turns over {}, their total is {}",
+            self.ui.card_name(&dealer.deck[1]),
+            hand_value(&dealer.deck)
         );
+        self.ui.show_message(&message);
+
+        // drawing can reshuffle the shoe, which needs all of self
+        let drawn = self.dealer_draws(&mut dealer.deck, live);
+        let value = hand_value(&dealer.deck);
+        self.dealer = Some(dealer);
+        drawn?;
+
+        if value.total > 21 {
+            self.ui.show_message("The dealer busted!");
+        } else {
+            self.ui
+                .show_message(&format!("The dealer stands on {}", value));
+        }
+
+        Ok(())
+    }
+
+    fn dealer_draws(&mut self, cards: &mut Vec<Card>, live: bool) -> Result<(), CardsError> {
+        loop {
+            let value = hand_value(cards);
+            let hit = live
+                && (value.total < 17
+                    || (value.total == 17 && value.soft && self.rules.dealer_hits_soft_17));
+
+            if !hit {
+                return Ok(());
+            }
 
-        console_clear!();
-        header_start!();
-        match choice {
-            1 => {
-                if let Some(card) = self.handle_hit(player_id) {
-                    self.players[player_index].deck.push(card);
-                } else {
-                    return true;
+            let card = self.deal_card()?;
+            cards.push(card);
+
+            let message = format!(
+                "The dealer drew {}, their total is now {}",
+                self.ui.card_name(&card),
+                hand_value(cards)
+            );
+            self.ui.show_message(&message);
+        }
+    }
+
+    // naturals stand right away, and are paid as soon as the dealer can't
+    // have one too
+    fn check_naturals(&mut self) -> Result<(), CardsError> {
+        let mut naturals = Vec::new();
+
+        for player in &self.players {
+            let hand = &self.hands(player.id)[0];
+
+            if hand.is_natural() && hand.outcome.is_none() {
+                naturals.push(player.id);
+            }
+        }
+
+        let upcard = self
+            .dealer
+            .as_ref()
+            .map(|x| x.deck[0].rank.blackjack_value());
+        let peek = self.rules.dealer_peeks && matches!(upcard, Some(10 | 11));
+
+        if naturals.is_empty() && !peek {
+            return Ok(());
+        }
+
+        self.ui.clear();
+        self.print_player_cards(None);
+        self.ui.show_message("");
+
+        for &player_id in &naturals {
+            self.hand_mut(player_id, 0)?.done = true;
+        }
+
+        let Some(dealer) = &mut self.dealer else {
+            for &player_id in &naturals {
+                let player = self.player_mut(player_id)?;
+                let message = format!("{} has blackjack!", player.name);
+                self.ui.show_message(&message);
+            }
+
+            return self.ui.pause();
+        };
+
+        if peek && is_natural(&dealer.deck) {
+            dealer.deck[1].visible = true;
+            self.ui
+                .show_message("The dealer checks the hole card and has blackjack!");
+
+            // nobody plays against a dealer blackjack
+            for hand in self.hands.values_mut().flatten() {
+                hand.done = true;
+            }
+
+            return self.ui.pause();
+        }
+
+        if peek {
+            self.ui
+                .show_message("The dealer checks the hole card, no blackjack.");
+        }
+
+        // without a peek, an ace or ten still could be blackjack
+        let dealer_blackjack = !peek && matches!(upcard, Some(10 | 11));
+
+        for player_id in naturals {
+            if dealer_blackjack {
+                let player = self.player_mut(player_id)?;
+                let message = format!("{} has blackjack!", player.name);
+                self.ui.show_message(&message);
+            } else {
+                self.settle(player_id, 0, Outcome::Blackjack)?;
+            }
+        }
+
+        self.ui.pause()
+    }
+
+    // pays the hand and closes it
+    fn settle(&mut self, player_id: i32, hand: usize, outcome: Outcome) -> Result<(), CardsError> {
+        let player = self
+            .players
+            .iter()
+            .find(|x| x.id == player_id)
+            .ok_or(CardsError::UnknownPlayer(player_id))?;
+        let name = self.hand_name(player, hand);
+
+        let settled = &self.hands(player_id)[hand];
+        let value = settled.value(self.rules.manual_aces);
+        let bet = settled.bet;
+        let payout = outcome.payout(bet, self.rules.blackjack_pays);
+
+        let message = match outcome {
+            Outcome::Win if settled.is_natural() => {
+                format!("{} takes even money and wins {}", name, payout - bet)
+            }
+            Outcome::Blackjack => {
+                format!("{} has blackjack and wins {}", name, payout - bet)
+            }
+            Outcome::Win => format!(
+                "{} beats the dealer with {} and wins {}",
+                name,
+                value,
+                payout - bet
+            ),
+            Outcome::Lose if value.total > 21 => format!("{} busted and loses {}", name, bet),
+            Outcome::Lose => format!("{} loses {} with {}", name, bet, value),
+            Outcome::Push => format!("{} pushes with {} and keeps their {}", name, value, bet),
+            Outcome::Surrender => format!("{} surrenders and gets {} back", name, payout),
+        };
+
+        self.player_mut(player_id)?.bankroll += payout;
+
+        let settled = self.hand_mut(player_id, hand)?;
+        settled.outcome = Some(outcome);
+        settled.done = true;
+
+        self.ui.show_message(&message);
+
+        Ok(())
+    }
+
+    fn resolve(&mut self) -> Result<(), CardsError> {
+        let Some(dealer) = &self.dealer else {
+            return Ok(());
+        };
+
+        self.ui.show_message("");
+
+        let dealer_value = hand_value(&dealer.deck);
+        let dealer_natural = is_natural(&dealer.deck);
+
+        for (player_id, cost) in std::mem::take(&mut self.insurance) {
+            let player = self.player_mut(player_id)?;
+
+            let message = if dealer_natural {
+                player.bankroll += cost * 3;
+                format!("{}'s insurance pays {}", player.name, cost * 2)
+            } else {
+                format!("{} loses their insurance of {}", player.name, cost)
+            };
+            self.ui.show_message(&message);
+        }
+
+        let mut results = Vec::new();
+
+        for player in &self.players {
+            for (i, hand) in self.hands(player.id).iter().enumerate() {
+                if hand.outcome.is_some() {
+                    continue;
                 }
 
-                false
+                let outcome = match (hand.is_natural(), dealer_natural) {
+                    (true, true) => Outcome::Push,
+                    (true, false) => Outcome::Blackjack,
+                    (false, true) => Outcome::Lose,
+                    (false, false) => {
+                        Outcome::against(hand.value(self.rules.manual_aces), dealer_value)
+                    }
+                };
+
+                results.push((player.id, i, outcome));
             }
-            2 => {
-                println!("You stood.");
-                true
+        }
+
+        for (player_id, hand, outcome) in results {
+            self.settle(player_id, hand, outcome)?;
+        }
+
+        Ok(())
+    }
+
+    // collects the cards and sends off the players who can't pay the minimum
+    fn end_hand(&mut self) {
+        let hands = std::mem::take(&mut self.hands);
+        let dealer = self
+            .dealer
+            .iter_mut()
+            .flat_map(|x| x.deck.drain(..))
+            .collect::<Vec<Card>>();
+        let cards = hands
+            .values()
+            .flatten()
+            .flat_map(|x| x.cards.iter())
+            .chain(dealer.iter())
+            .map(|x| Card {
+                visible: true,
+                ..*x
+            })
+            .collect::<Vec<Card>>();
+
+        if let Some(counter) = &mut self.counter {
+            counter.see_all(&cards);
+        }
+
+        self.shoe.discard_all(cards);
+        self.last_hand = HandRecord { hands, dealer };
+        self.hands_played += 1;
+
+        let mut index = 0;
+
+        while index < self.players.len() {
+            if self.players[index].bankroll >= self.rules.table_min {
+                index += 1;
+                continue;
             }
-            _ => panic!("Invalid choice"),
+
+            let player = self.players.remove(index);
+            self.ui.show_message(&format!(
+                "{} is out of chips and leaves the table",
+                player.name
+            ));
+            self.left_players.push(player);
+        }
+
+        self.ui
+            .show_message(&format!("\nStandings after hand {}:", self.hands_played));
+
+        for standing in self.standings() {
+            self.ui
+                .show_message(&format!("{}: {} chips", standing.name, standing.score));
         }
     }
 
-    fn get_hand_total(hand: &[Card]) -> i32 {
-        hand.iter().map(|i| i.blackjack_cmp_val().unwrap()).sum()
+    // a table that started with humans closes when the last one leaves
+    fn table_closed(&self) -> bool {
+        let had_humans = self
+            .players
+            .iter()
+            .chain(self.left_players.iter())
+            .any(|x| x.controller.is_human());
+
+        self.players.is_empty()
+            || (had_humans && !self.players.iter().any(|x| x.controller.is_human()))
+    }
+
+    fn game_over(&self) -> RoundOutcome {
+        let standings = self.standings();
+
+        RoundOutcome::GameOver {
+            winners: self.winners(&standings),
+            standings,
+        }
     }
 }
 
-impl Game for BlackJack {
-    fn round(&mut self) {
+impl<U: UserInterface> Game for BlackJack<U> {
+    fn round(&mut self) -> Result<RoundOutcome, CardsError> {
+        if self.dealer.is_some() {
+            self.betting()?;
+
+            if self.table_closed() {
+                return Ok(self.game_over());
+            }
+        }
+
+        self.deal()?;
+        self.offer_insurance()?;
+        self.check_naturals()?;
+
         let player_ids = self.players.iter().map(|x| x.id).collect::<Vec<i32>>();
 
         for id in player_ids {
-            loop {
-                console_clear!();
-                header_start!();
+            let human = self
+                .players
+                .iter()
+                .any(|x| x.id == id && x.controller.is_human());
+
+            // splitting adds hands while the player is playing them
+            let mut hand = 0;
 
-                let do_break = self.turn(id);
+            while hand < self.hands(id).len() {
+                if self.hands(id)[hand].done {
+                    hand += 1;
+                    continue;
+                }
 
-                header_end!();
-                prompt!("Press enter to continue...");
+                self.ui.clear();
 
-                if do_break {
-                    break;
+                if !human {
+                    self.print_player_cards(None);
                 }
-            }
-        }
 
-        let post_players = &mut self.players;
+                loop {
+                    let done = self.turn(id, hand)?;
 
-        console_clear!();
-        header_start!();
-        if post_players.is_empty() {
-            println!("There were no winners, all of them busted");
-        } else {
-            let mut winners = Vec::new();
+                    // computer players play their whole hand before the pause
+                    if human || done {
+                        self.ui.pause()?;
+                    }
 
-            post_players.sort_by(|a, b| {
-                BlackJack::get_hand_total(&b.deck).cmp(&BlackJack::get_hand_total(&a.deck))
-            });
-    
-            let highest = BlackJack::get_hand_total(&post_players[0].deck);
-    
-            for player in post_players {
-                if BlackJack::get_hand_total(&player.deck) == highest {
-                    winners.push(player);
+                    if done {
+                        break;
+                    }
+
+                    if human {
+                        self.ui.clear();
+                    }
                 }
-            }
 
-            println!("Winners are:");
-            for player in winners {
-                println!(
-                    "{} with a total score of {}",
-                    player.name,
-                    BlackJack::get_hand_total(&player.deck)
-                );
+                hand += 1;
             }
         }
 
-        header_end!();
-        prompt!("Press enter to exit...");
-        console_clear!();
+        // without a dealer the players only play one hand against each other
+        if self.dealer.is_none() {
+            return Ok(self.game_over());
+        }
+
+        self.dealer_turn()?;
+        self.print_player_cards(None);
+        self.resolve()?;
+        self.end_hand();
+        self.ui.pause()?;
+
+        if self.table_closed() {
+            return Ok(self.game_over());
+        }
 
-        std::process::exit(0);
+        Ok(RoundOutcome::Continue)
     }
 
     fn get_players(&self) -> &Vec<Player> {
         &self.players
     }
+
+    fn standings(&self) -> Vec<Standing> {
+        let mut standings = if self.dealer.is_some() {
+            // chips still on the table count for their owner
+            self.players
+                .iter()
+                .chain(self.left_players.iter())
+                .map(|x| {
+                    let on_table = self
+                        .hands(x.id)
+                        .iter()
+                        .filter(|x| x.outcome.is_none())
+                        .map(|x| x.bet)
+                        .sum::<i32>()
+                        + self.insurance.get(&x.id).unwrap_or(&0);
+
+                    Standing {
+                        player_id: x.id,
+                        name: x.name.clone(),
+                        score: x.bankroll + on_table,
+                        out: x.bankroll + on_table < self.rules.table_min,
+                    }
+                })
+                .collect::<Vec<Standing>>()
+        } else {
+            self.players
+                .iter()
+                .map(|x| {
+                    let total = self
+                        .hands(x.id)
+                        .first()
+                        .map_or(0, |x| x.value(self.rules.manual_aces).total);
+
+                    Standing {
+                        player_id: x.id,
+                        name: x.name.clone(),
+                        score: total,
+                        out: total > 21,
+                    }
+                })
+                .collect::<Vec<Standing>>()
+        };
+
+        standings.sort_by_key(|x| (x.out, std::cmp::Reverse(x.score)));
+        standings
+    }
+
+    // against the dealer everyone who made money wins
+    fn winners(&self, standings: &[Standing]) -> Vec<i32> {
+        if self.dealer.is_none() {
+            return leaders(standings);
+        }
+
+        standings
+            .iter()
+            .filter(|x| x.score > self.rules.starting_bankroll)
+            .map(|x| x.player_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::card::parse_hand;
+    use crate::deck::Deck;
+    use crate::ui::ScriptedUi;
+
+    fn value(hand: &str) -> (i32, bool) {
+        let value = hand_value(&parse_hand(hand).unwrap());
+
+        (value.total, value.soft)
+    }
+
+    // one human player whose cards, then the dealer's, come off the top
+    fn stacked_game(cards: &str, answers: &[&str]) -> BlackJack<ScriptedUi> {
+        stacked_table(
+            BlackJackRules::default(),
+            ControllerKind::Human,
+            cards,
+            answers,
+        )
+    }
+
+    fn stacked_table(
+        rules: BlackJackRules,
+        controller: ControllerKind,
+        cards: &str,
+        answers: &[&str],
+    ) -> BlackJack<ScriptedUi> {
+        let mut rng = StdRng::seed_from_u64(1);
+        let seats = vec![("Ann".to_string(), controller)];
+        let mut game =
+            BlackJack::with_rules(seats, rules, &mut rng, ScriptedUi::new(answers)).unwrap();
+
+        let mut cards = parse_hand(cards).unwrap();
+        cards.extend(Card::new_deck());
+        game.shoe = Deck::from_cards(cards, &mut rng);
+
+        game
+    }
+
+    #[test]
+    fn counts_aces_as_high_as_they_can_go() {
+        assert_eq!(value("as 6h"), (17, true));
+        assert_eq!(value("as 6h kd"), (17, false));
+        assert_eq!(value("as ah 9c"), (21, true));
+        assert_eq!(value("ks qh 5c"), (25, false));
+    }
+
+    #[test]
+    fn shows_soft_totals() {
+        let show = |x: &str| hand_value(&parse_hand(x).unwrap()).to_string();
+
+        assert_eq!(show("as 6h"), "soft 17");
+        assert_eq!(show("as 6h kd"), "17");
+    }
+
+    #[test]
+    fn pays_blackjack_rounded_down() {
+        assert_eq!(Payout::ThreeToTwo.pay(10), 15);
+        assert_eq!(Payout::ThreeToTwo.pay(5), 7);
+        assert_eq!(Payout::SixToFive.pay(10), 12);
+    }
+
+    #[test]
+    fn pays_outcomes_with_the_bet() {
+        let pays = Payout::ThreeToTwo;
+
+        assert_eq!(Outcome::Win.payout(10, pays), 20);
+        assert_eq!(Outcome::Blackjack.payout(10, pays), 25);
+        assert_eq!(Outcome::Lose.payout(10, pays), 0);
+        assert_eq!(Outcome::Push.payout(10, pays), 10);
+        assert_eq!(Outcome::Surrender.payout(10, pays), 5);
+    }
+
+    #[test]
+    fn rejects_a_bankroll_below_the_minimum() {
+        let rules = BlackJackRules {
+            starting_bankroll: 5,
+            ..BlackJackRules::default()
+        };
+        let seats = vec![("Ann".to_string(), ControllerKind::Human)];
+        let game = BlackJack::with_rules(
+            seats,
+            rules,
+            &mut StdRng::seed_from_u64(1),
+            ScriptedUi::default(),
+        );
+
+        assert!(matches!(game, Err(CardsError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn finds_naturals() {
+        assert!(is_natural(&parse_hand("as kh").unwrap()));
+        assert!(!is_natural(&parse_hand("as 5h 5d").unwrap()));
+    }
+
+    #[test]
+    fn pays_a_natural_at_the_deal() {
+        let mut game = stacked_game("as kh 9c 7d", &["10"]);
+
+        assert!(matches!(game.round(), Ok(RoundOutcome::Continue)));
+        assert_eq!(game.players[0].bankroll, 1015);
+        assert_eq!(
+            game.last_hand().hands[&0][0].outcome,
+            Some(Outcome::Blackjack)
+        );
+    }
+
+    #[test]
+    fn plays_a_scripted_round() {
+        // bet 20, hit to 21, stand, and the dealer busts drawing to 16
+        let mut game = stacked_game("10s 6h 9c 7d 5s kh", &["20", "1", "2"]);
+
+        assert!(matches!(game.round(), Ok(RoundOutcome::Continue)));
+        assert_eq!(game.players[0].bankroll, 1020);
+        assert_eq!(game.last_hand().hands[&0][0].outcome, Some(Outcome::Win));
+        assert!(game.ui.output.iter().any(|x| x == "The dealer busted!"));
+    }
+
+    #[test]
+    fn closes_split_aces_once_the_table_is_full() {
+        let rules = BlackJackRules {
+            resplit_aces: true,
+            max_hands: 3,
+            ..BlackJackRules::default()
+        };
+        // both hands of the first split are aces again, the last split fills
+        // the table and leaves the other pair of aces with nothing to do
+        let mut game = stacked_table(
+            rules,
+            ControllerKind::BasicStrategy,
+            "as ah 6c 10d ad ac 5s 4h",
+            &[],
+        );
+
+        assert!(matches!(game.round(), Ok(RoundOutcome::Continue)));
+
+        let hands = &game.last_hand().hands[&0];
+        assert_eq!(hands.len(), 3);
+        assert!(hands.iter().all(|x| x.cards.len() == 2));
+    }
+
+    #[test]
+    fn resets_the_count_when_the_shoe_runs_out() {
+        let rules = BlackJackRules {
+            counting: Some(CountingKind::HiLo),
+            ..BlackJackRules::default()
+        };
+        // only the cards for this hand, the dealer draws to 16 from the
+        // reshuffled low cards
+        let mut game = stacked_table(rules, ControllerKind::Human, "", &["20", "1", "2"]);
+        game.shoe = Deck::from_cards(
+            parse_hand("10s 6h 9c 7d 5s").unwrap(),
+            &mut StdRng::seed_from_u64(1),
+        );
+
+        let low = parse_hand("2c 3c 4c 5c 6c").unwrap();
+        game.counter.as_mut().unwrap().see_all(&low);
+        game.shoe.discard_all(low);
+
+        assert!(matches!(game.round(), Ok(RoundOutcome::Continue)));
+        assert!(game
+            .ui
+            .output
+            .iter()
+            .any(|x| x == "The dealer reshuffles the shoe."));
+        // 10, 6 and 5 for the player and a low card for the dealer, without
+        // the low cards seen before the reshuffle
+        assert_eq!(game.counter.as_ref().unwrap().running(), 2);
+    }
+
+    #[test]
+    fn offers_surrender_only_after_a_peek() {
+        let no_peek = BlackJackRules {
+            dealer_peeks: false,
+            ..BlackJackRules::default()
+        };
+
+        // 16 against a ten, then against a nine
+        for (rules, cards, surrender) in [
+            (BlackJackRules::default(), "10s 6h kc 7d", true),
+            (no_peek.clone(), "10s 6h kc 7d", false),
+            (no_peek, "10s 6h 9c 7d", true),
+        ] {
+            let mut game = stacked_table(rules, ControllerKind::Human, cards, &["10"]);
+            game.betting().unwrap();
+            game.deal().unwrap();
+
+            let actions = game.actions(&game.players[0], 0);
+            assert_eq!(actions.contains(&Action::Surrender), surrender);
+        }
+    }
 }
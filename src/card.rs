@@ -1,152 +1,410 @@
-use rand::{random, seq::SliceRandom, thread_rng};
+use rand::{
+    distributions::{Distribution, Standard},
+    seq::SliceRandom,
+    Rng,
+};
 use std::{
     error::Error,
     fmt::{self, Display, Formatter},
+    str::FromStr,
 };
 
-pub trait ValueIDGen {
-    fn gen_value_by_id(id: i32) -> Result<String, Box<dyn Error>>;
+use crate::error::CardsError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Suit {
+    Spades,
+    Hearts,
+    Clubs,
+    Diamonds,
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Clubs, Suit::Diamonds];
+
+    pub fn iter() -> impl Iterator<Item = Suit> {
+        Suit::ALL.into_iter()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Suit::Spades => "Spades",
+            Suit::Hearts => "Hearts",
+            Suit::Clubs => "Clubs",
+            Suit::Diamonds => "Diamonds",
+        }
+    }
+
+    pub fn symbol(&self) -> char {
+        match self {
+            Suit::Spades => '\u{2660}',
+            Suit::Hearts => '\u{2665}',
+            Suit::Clubs => '\u{2663}',
+            Suit::Diamonds => '\u{2666}',
+        }
+    }
+
+    pub fn is_red(&self) -> bool {
+        matches!(self, Suit::Hearts | Suit::Diamonds)
+    }
 }
 
-#[derive(Clone)]
+impl Distribution<Suit> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Suit {
+        Suit::ALL[rng.gen_range(0..Suit::ALL.len())]
+    }
+}
+
+impl Display for Suit {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rank {
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+    Ace,
+}
+
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+        Rank::Ace,
+    ];
+
+    pub fn iter() -> impl Iterator<Item = Rank> {
+        Rank::ALL.into_iter()
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rank::Two => "2",
+            Rank::Three => "3",
+            Rank::Four => "4",
+            Rank::Five => "5",
+            Rank::Six => "6",
+            Rank::Seven => "7",
+            Rank::Eight => "8",
+            Rank::Nine => "9",
+            Rank::Ten => "10",
+            Rank::Jack => "Jack",
+            Rank::Queen => "Queen",
+            Rank::King => "King",
+            Rank::Ace => "Ace",
+        }
+    }
+
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Rank::Jack => "J",
+            Rank::Queen => "Q",
+            Rank::King => "K",
+            Rank::Ace => "A",
+            rank => rank.name(),
+        }
+    }
+}
+
+impl Distribution<Rank> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Rank {
+        Rank::ALL[rng.gen_range(0..Rank::ALL.len())]
+    }
+}
+
+impl Display for Rank {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Card {
-    pub suit: String,
-    pub suit_id: i32,
-    pub value: String,
-    pub value_id: i32,
+    pub suit: Suit,
+    pub rank: Rank,
     pub visible: bool,
 }
 
 impl Card {
-    pub fn new(suit_id: i32, value_id: i32) -> Result<Card, Box<dyn Error>> {
-        let mut card = Card {
-            suit: String::new(),
-            suit_id,
-            value: String::new(),
-            value_id,
+    pub fn new(suit: Suit, rank: Rank) -> Card {
+        Card {
+            suit,
+            rank,
             visible: true,
-        };
-
-        card.refresh().unwrap();
-        Ok(card)
+        }
     }
 
-    pub fn new_random() -> Result<Card, Box<dyn Error>> {
-        let suit_id = random::<i32>() % 4;
-        let value_id = random::<i32>() % 13;
-        Card::new(suit_id, value_id)
+    pub fn new_random(rng: &mut impl Rng) -> Card {
+        rng.gen()
     }
 
-    pub fn refresh(&mut self) -> Result<(), Box<dyn Error>> {
-        self.suit = match self.suit_id {
-            0 => "Spades",
-            1 => "Hearts",
-            2 => "Clubs",
-            3 => "Diamonds",
-            _ => return Err("E_INVALID_SUIT".into()),
+    pub fn new_random_distinct(rng: &mut impl Rng, count: usize) -> Result<Vec<Card>, CardsError> {
+        if count > 52 {
+            return Err(CardsError::DeckExhausted);
         }
-        .to_string();
-
-        self.value = match self.value_id {
-            0 => "2",
-            1 => "3",
-            2 => "4",
-            3 => "5",
-            4 => "6",
-            5 => "7",
-            6 => "8",
-            7 => "9",
-            8 => "10",
-            9 => "Jack",
-            10 => "Queen",
-            11 => "King",
-            12 => "Ace",
-            _ => return Err("E_INVALID_VALUE".into()),
-        }
-        .to_string();
 
-        Ok(())
+        Ok(Card::new_deck()
+            .choose_multiple(rng, count)
+            .copied()
+            .collect())
     }
 
-    pub fn refresh_blackjack(&mut self) -> Result<(), Box<dyn Error>> {
-        self.suit = match self.suit_id {
-            0 => "Spades",
-            1 => "Hearts",
-            2 => "Clubs",
-            3 => "Diamonds",
-            _ => return Err("E_INVALID_SUIT".into()),
-        }
-        .to_string();
+    pub fn new_random_excluding(rng: &mut impl Rng, exclude: &[Card]) -> Result<Card, CardsError> {
+        let deck = Card::new_deck()
+            .into_iter()
+            .filter(|card| !exclude.iter().any(|x| x.same_card(card)))
+            .collect::<Vec<Card>>();
+
+        deck.choose(rng).copied().ok_or(CardsError::DeckExhausted)
+    }
+
+    pub fn glyph(&self) -> char {
+        let base = match self.suit {
+            Suit::Spades => 0x1F0A0,
+            Suit::Hearts => 0x1F0B0,
+            Suit::Diamonds => 0x1F0C0,
+            Suit::Clubs => 0x1F0D0,
+        };
+
+        // the unicode block has a knight between jack and queen
+        let offset = match self.rank {
+            Rank::Ace => 1,
+            Rank::Jack => 11,
+            Rank::Queen => 13,
+            Rank::King => 14,
+            rank => rank as u32 + 2,
+        };
+
+        // every rank and suit is in the block, so the fallback never shows
+        char::from_u32(base + offset).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
 
-        let v_id = self.value_id.to_string();
-        self.value = match self.value_id {
-            1 => "Ace (as 1)",
-            2..=10 => &v_id,
-            11 => "Jack (as 10)",
-            12 => "Queen (as 10)",
-            13 => "King (as 10)",
-            14 => "Ace (as 11)",
-            _ => return Err("E_INVALID_VALUE".into()),
-        }.to_string();
+    pub fn from_glyph(glyph: char) -> Option<Card> {
+        Card::new_deck()
+            .into_iter()
+            .find(|card| card.glyph() == glyph)
+    }
 
-        Ok(())
+    // equal face, regardless of visibility
+    pub fn same_card(&self, other: &Card) -> bool {
+        self.suit == other.suit && self.rank == other.rank
     }
 
     pub fn new_deck() -> Vec<Card> {
         let mut deck = Vec::new();
 
-        for i in 0..4 {
-            for j in 0..13 {
-                deck.push(Card::new(i, j).unwrap());
+        for suit in Suit::iter() {
+            for rank in Rank::iter() {
+                deck.push(Card::new(suit, rank));
             }
         }
 
         deck
     }
+}
 
-    pub fn new_random_deck() -> Vec<Card> {
-        let mut deck = Card::new_deck();
-        deck.shuffle(&mut thread_rng());
+// blackjack impl
+impl Rank {
+    pub fn blackjack_value(&self) -> i32 {
+        match self {
+            Rank::Ace => 11,
+            Rank::Jack | Rank::Queen | Rank::King => 10,
+            rank => *rank as i32 + 2,
+        }
+    }
+}
 
-        deck
+impl Distribution<Card> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Card {
+        Card::new(rng.gen(), rng.gen())
     }
+}
 
-    pub fn copy(&self) -> Card {
-        Card {
-            suit: self.suit.clone(),
-            suit_id: self.suit_id,
-            value: self.value.clone(),
-            value_id: self.value_id,
-            visible: self.visible,
+impl Display for Card {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} of {}", self.rank, self.suit)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseCardError {
+    Empty,
+    InvalidRank(String),
+    InvalidSuit(String),
+    InvalidCard(String),
+}
+
+impl Display for ParseCardError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ParseCardError::Empty => write!(f, "No card given"),
+            ParseCardError::InvalidRank(x) => write!(f, "\"{}\" is not a card rank", x),
+            ParseCardError::InvalidSuit(x) => write!(f, "\"{}\" is not a card suit", x),
+            ParseCardError::InvalidCard(x) => write!(f, "\"{}\" is not a card", x),
         }
     }
 }
 
-// blackjack impl
-impl Card {
-    pub fn blackjack_cmp_val(&self) -> Result<i32, Box<dyn Error>> {
-        match self.value_id {
-            1..=10 => Ok(self.value_id),
-            11..=13 => Ok(10),
-            14 => Ok(11),
-            _ => Err("E_INVALID_VALUE".into()),
+impl Error for ParseCardError {}
+
+impl FromStr for Suit {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Suit, ParseCardError> {
+        match s.trim().to_lowercase().as_str() {
+            "s" | "spade" | "spades" | "\u{2660}" | "\u{2664}" => Ok(Suit::Spades),
+            "h" | "heart" | "hearts" | "\u{2665}" | "\u{2661}" => Ok(Suit::Hearts),
+            "c" | "club" | "clubs" | "\u{2663}" | "\u{2667}" => Ok(Suit::Clubs),
+            "d" | "diamond" | "diamonds" | "\u{2666}" | "\u{2662}" => Ok(Suit::Diamonds),
+            _ => Err(ParseCardError::InvalidSuit(s.trim().to_string())),
         }
     }
+}
 
-    pub fn new_deck_blackjack() -> Vec<Card> {
-        let mut deck = Card::new_random_deck();
+impl FromStr for Rank {
+    type Err = ParseCardError;
 
-        for card in deck.iter_mut() {
-            card.value_id += 2;
-            card.refresh_blackjack().expect("E_UNKNOWN");
+    fn from_str(s: &str) -> Result<Rank, ParseCardError> {
+        match s.trim().to_lowercase().as_str() {
+            "2" | "two" => Ok(Rank::Two),
+            "3" | "three" => Ok(Rank::Three),
+            "4" | "four" => Ok(Rank::Four),
+            "5" | "five" => Ok(Rank::Five),
+            "6" | "six" => Ok(Rank::Six),
+            "7" | "seven" => Ok(Rank::Seven),
+            "8" | "eight" => Ok(Rank::Eight),
+            "9" | "nine" => Ok(Rank::Nine),
+            "10" | "t" | "ten" => Ok(Rank::Ten),
+            "j" | "jack" => Ok(Rank::Jack),
+            "q" | "queen" => Ok(Rank::Queen),
+            "k" | "king" => Ok(Rank::King),
+            "a" | "1" | "ace" => Ok(Rank::Ace),
+            _ => Err(ParseCardError::InvalidRank(s.trim().to_string())),
         }
+    }
+}
 
-        deck
+// accepts "Queen of Hearts", "QH", "10s", "Ah", "Q♥" and the unicode card glyphs
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    fn from_str(s: &str) -> Result<Card, ParseCardError> {
+        let s = s.trim();
+        let mut chars = s.chars();
+
+        let last = match chars.next_back() {
+            Some(x) => x,
+            None => return Err(ParseCardError::Empty),
+        };
+
+        if chars.as_str().is_empty() {
+            return Card::from_glyph(last)
+                .ok_or_else(|| ParseCardError::InvalidCard(s.to_string()));
+        }
+
+        let words = s.split_whitespace().collect::<Vec<&str>>();
+
+        if let [rank, of, suit] = words[..] {
+            if of.eq_ignore_ascii_case("of") {
+                return Ok(Card::new(suit.parse()?, rank.parse()?));
+            }
+        }
+
+        if words.len() > 1 {
+            return Err(ParseCardError::InvalidCard(s.to_string()));
+        }
+
+        Ok(Card::new(
+            last.to_string().parse()?,
+            chars.as_str().parse()?,
+        ))
     }
 }
 
-impl Display for Card {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{} of {}", self.value, self.suit)
+// cards are separated by commas if there are any, otherwise by whitespace
+pub fn parse_hand(s: &str) -> Result<Vec<Card>, ParseCardError> {
+    if s.contains(',') {
+        s.split(',')
+            .filter(|x| !x.trim().is_empty())
+            .map(str::parse)
+            .collect()
+    } else {
+        s.split_whitespace().map(str::parse).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_long_and_compact_cards() {
+        let queen = Card::new(Suit::Hearts, Rank::Queen);
+
+        assert_eq!("Queen of Hearts".parse(), Ok(queen));
+        assert_eq!("queen OF hearts".parse(), Ok(queen));
+        assert_eq!("QH".parse(), Ok(queen));
+        assert_eq!("Q\u{2665}".parse(), Ok(queen));
+        assert_eq!("10s".parse(), Ok(Card::new(Suit::Spades, Rank::Ten)));
+        assert_eq!(" Ad ".parse(), Ok(Card::new(Suit::Diamonds, Rank::Ace)));
+    }
+
+    #[test]
+    fn parses_every_glyph() {
+        for card in Card::new_deck() {
+            assert_eq!(card.glyph().to_string().parse(), Ok(card));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_cards() {
+        assert_eq!("".parse::<Card>(), Err(ParseCardError::Empty));
+        assert_eq!(
+            "Zh".parse::<Card>(),
+            Err(ParseCardError::InvalidRank("Z".to_string()))
+        );
+        assert_eq!(
+            "Queen of Hats".parse::<Card>(),
+            Err(ParseCardError::InvalidSuit("Hats".to_string()))
+        );
+        assert_eq!(
+            "Q".parse::<Card>(),
+            Err(ParseCardError::InvalidCard("Q".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_hands() {
+        let hand = vec![
+            Card::new(Suit::Spades, Rank::Ace),
+            Card::new(Suit::Hearts, Rank::Ten),
+            Card::new(Suit::Clubs, Rank::King),
+        ];
+
+        assert_eq!(parse_hand("AS, 10h ,King of Clubs,"), Ok(hand.clone()));
+        assert_eq!(parse_hand("as 10h kc"), Ok(hand));
+        assert!(parse_hand("as xx").is_err());
     }
 }
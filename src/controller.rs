@@ -0,0 +1,365 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::card::{Card, Rank};
+use crate::counting::Count;
+use crate::error::CardsError;
+use crate::ui::{Option, UserInterface};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Hit,
+    Stand,
+    Double,
+    Split,
+    Surrender,
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Hit => "Hit",
+            Action::Stand => "Stand",
+            Action::Double => "Double down",
+            Action::Split => "Split",
+            Action::Surrender => "Surrender",
+        }
+    }
+}
+
+// everything a player is allowed to see when it is their turn
+pub struct TurnView<'a> {
+    pub name: &'a str,
+    pub cards: &'a [Card],
+    pub total: i32,
+    // an ace is still counted as 11
+    pub soft: bool,
+    // the dealer's face-up card, when playing against a dealer
+    pub upcard: std::option::Option<Card>,
+    // what the rules allow for this hand right now
+    pub actions: &'a [Action],
+    // the basic strategy play, when hints are on
+    pub hint: std::option::Option<Action>,
+    // when practising card counting
+    pub count: std::option::Option<Count>,
+    // counted from 1, players have more than one hand after a split
+    pub hand: usize,
+    pub hands: usize,
+}
+
+pub struct BetView<'a> {
+    pub name: &'a str,
+    pub bankroll: i32,
+    pub min: i32,
+    pub max: i32,
+}
+
+pub struct InsuranceView<'a> {
+    pub name: &'a str,
+    pub bet: i32,
+    // insurance on a natural is even money
+    pub natural: bool,
+}
+
+pub trait PlayerController {
+    // none to leave the table with the bankroll
+    fn bet(
+        &mut self,
+        _ui: &mut dyn UserInterface,
+        view: &BetView,
+    ) -> Result<std::option::Option<i32>, CardsError> {
+        Ok(Some(view.min))
+    }
+
+    fn insurance(
+        &mut self,
+        _ui: &mut dyn UserInterface,
+        _view: &InsuranceView,
+    ) -> Result<bool, CardsError> {
+        Ok(false)
+    }
+
+    fn action(&mut self, ui: &mut dyn UserInterface, view: &TurnView)
+        -> Result<Action, CardsError>;
+
+    // total is the hand without the new ace
+    fn ace_value(
+        &mut self,
+        ui: &mut dyn UserInterface,
+        name: &str,
+        total: i32,
+    ) -> Result<i32, CardsError>;
+
+    fn is_human(&self) -> bool {
+        false
+    }
+}
+
+pub struct HumanController;
+
+impl PlayerController for HumanController {
+    fn bet(
+        &mut self,
+        ui: &mut dyn UserInterface,
+        view: &BetView,
+    ) -> Result<std::option::Option<i32>, CardsError> {
+        let max = view.max.min(view.bankroll);
+        let ask = format!(
+            "{}, you have {} chips. How much do you bet ({} to {}, 0 to cash out)? ",
+            view.name, view.bankroll, view.min, max
+        );
+
+        loop {
+            match ui.ask_number(&ask, 0..=max, Some(view.min))? {
+                0 => return Ok(None),
+                bet if bet >= view.min => return Ok(Some(bet)),
+                _ => ui.show_message(&format!("The table minimum is {}.", view.min)),
+            }
+        }
+    }
+
+    fn action(
+        &mut self,
+        ui: &mut dyn UserInterface,
+        view: &TurnView,
+    ) -> Result<Action, CardsError> {
+        let soft = if view.soft { "soft " } else { "" };
+        let hand = if view.hands > 1 {
+            format!(", hand {} of {}", view.hand, view.hands)
+        } else {
+            String::new()
+        };
+        let ask = format!(
+            "{}'s turn{}. Your total is {}{}. What do you want to do?",
+            view.name, hand, soft, view.total
+        );
+
+        let mut options = view
+            .actions
+            .iter()
+            .enumerate()
+            .map(|(i, x)| Option {
+                name: x.name(),
+                value: i as i32 + 1,
+            })
+            .collect::<Vec<Option>>();
+        let hint_choice = view.actions.len() as i32 + 1;
+        let count_choice = hint_choice + 1;
+
+        if view.hint.is_some() {
+            options.push(Option {
+                name: "Hint",
+                value: hint_choice,
+            });
+        }
+
+        if view.count.is_some() {
+            options.push(Option {
+                name: "Show count",
+                value: count_choice,
+            });
+        }
+
+        ui.show_message("");
+
+        loop {
+            let choice = ui.choose_option(&ask, &options)?;
+
+            match (view.hint, view.count) {
+                (Some(hint), _) if choice == hint_choice => {
+                    ui.show_message(&format!("Basic strategy says: {}.", hint.name()));
+                }
+                (_, Some(count)) if choice == count_choice => {
+                    ui.show_message(&count.to_string());
+                }
+                _ => {
+                    return view
+                        .actions
+                        .get((choice - 1) as usize)
+                        .copied()
+                        .ok_or(CardsError::InvalidOption(choice))
+                }
+            }
+        }
+    }
+
+    fn insurance(
+        &mut self,
+        ui: &mut dyn UserInterface,
+        view: &InsuranceView,
+    ) -> Result<bool, CardsError> {
+        if view.natural {
+            ui.ask_yes_no(
+                &format!("{}, you have blackjack. Take even money?", view.name),
+                Some(false),
+            )
+        } else {
+            ui.ask_yes_no(
+                &format!("{}, take insurance for {}?", view.name, view.bet / 2),
+                Some(false),
+            )
+        }
+    }
+
+    fn ace_value(
+        &mut self,
+        ui: &mut dyn UserInterface,
+        name: &str,
+        total: i32,
+    ) -> Result<i32, CardsError> {
+        let ask = format!(
+            "{}, you have an ace. Your current hand total is {}. What do you want it to be worth (1 or 11)? ",
+            name,
+            total
+        );
+
+        loop {
+            match ui.ask_number(&ask, 1..=11, None)? {
+                value @ (1 | 11) => return Ok(value),
+                _ => ui.show_message("An ace is worth either 1 or 11."),
+            }
+        }
+    }
+
+    fn is_human(&self) -> bool {
+        true
+    }
+}
+
+// the dealer's rule: keep hitting until the total reaches the threshold
+pub struct HitBelowController {
+    pub threshold: i32,
+}
+
+impl PlayerController for HitBelowController {
+    fn action(
+        &mut self,
+        _ui: &mut dyn UserInterface,
+        view: &TurnView,
+    ) -> Result<Action, CardsError> {
+        if view.total < self.threshold {
+            Ok(Action::Hit)
+        } else {
+            Ok(Action::Stand)
+        }
+    }
+
+    fn ace_value(
+        &mut self,
+        _ui: &mut dyn UserInterface,
+        _name: &str,
+        total: i32,
+    ) -> Result<i32, CardsError> {
+        Ok(best_ace_value(total))
+    }
+}
+
+// plays the strategy module's recommendation, and without a dealer hits or
+// stands from a simpler chart
+pub struct BasicStrategyController;
+
+impl PlayerController for BasicStrategyController {
+    fn action(
+        &mut self,
+        _ui: &mut dyn UserInterface,
+        view: &TurnView,
+    ) -> Result<Action, CardsError> {
+        if let Some(hint) = view.hint {
+            return Ok(hint);
+        }
+
+        // without a dealer, assume the other players are showing a ten
+        let upcard = view.upcard.map_or(10, |x| x.rank.blackjack_value());
+
+        let hit = if view.soft {
+            match view.total {
+                ..=17 => true,
+                18 => upcard >= 9,
+                _ => false,
+            }
+        } else {
+            match view.total {
+                ..=11 => true,
+                12 => !(4..=6).contains(&upcard),
+                13..=16 => upcard >= 7,
+                _ => false,
+            }
+        };
+
+        if hit {
+            Ok(Action::Hit)
+        } else {
+            Ok(Action::Stand)
+        }
+    }
+
+    fn ace_value(
+        &mut self,
+        _ui: &mut dyn UserInterface,
+        _name: &str,
+        total: i32,
+    ) -> Result<i32, CardsError> {
+        Ok(best_ace_value(total))
+    }
+}
+
+fn best_ace_value(total: i32) -> i32 {
+    if total + Rank::Ace.blackjack_value() <= 21 {
+        11
+    } else {
+        1
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ControllerKind {
+    #[default]
+    Human,
+    HitBelow(i32),
+    BasicStrategy,
+}
+
+impl ControllerKind {
+    pub fn controller(self) -> Box<dyn PlayerController> {
+        match self {
+            ControllerKind::Human => Box::new(HumanController),
+            ControllerKind::HitBelow(threshold) => Box::new(HitBelowController { threshold }),
+            ControllerKind::BasicStrategy => Box::new(BasicStrategyController),
+        }
+    }
+
+    pub fn is_human(self) -> bool {
+        self == ControllerKind::Human
+    }
+}
+
+impl Display for ControllerKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ControllerKind::Human => write!(f, "human"),
+            ControllerKind::HitBelow(17) => write!(f, "cpu"),
+            ControllerKind::HitBelow(x) => write!(f, "cpu{}", x),
+            ControllerKind::BasicStrategy => write!(f, "basic"),
+        }
+    }
+}
+
+// "human", "basic", "cpu" (hits below 17) or "cpu15" for another threshold
+impl FromStr for ControllerKind {
+    type Err = CardsError;
+
+    fn from_str(s: &str) -> Result<ControllerKind, CardsError> {
+        let s = s.trim().to_lowercase();
+
+        match s.as_str() {
+            "human" => Ok(ControllerKind::Human),
+            "basic" => Ok(ControllerKind::BasicStrategy),
+            "cpu" => Ok(ControllerKind::HitBelow(17)),
+            _ => s
+                .strip_prefix("cpu")
+                .and_then(|x| x.parse().ok())
+                .map(ControllerKind::HitBelow)
+                .ok_or(CardsError::InvalidInput(s)),
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::card::{Card, Rank};
+use crate::error::CardsError;
+
+// a card counting system gives every card a tag that is added to the count
+pub trait CountingSystem {
+    fn name(&self) -> &'static str;
+
+    fn tag(&self, rank: Rank) -> i32;
+
+    // the running count for a fresh shoe
+    fn initial_count(&self, _decks: usize) -> i32 {
+        0
+    }
+
+    // a balanced system counts a full deck back to zero, which is what makes
+    // the true count meaningful
+    fn is_balanced(&self) -> bool {
+        true
+    }
+}
+
+pub struct HiLo;
+
+impl CountingSystem for HiLo {
+    fn name(&self) -> &'static str {
+        "Hi-Lo"
+    }
+
+    fn tag(&self, rank: Rank) -> i32 {
+        match rank.blackjack_value() {
+            2..=6 => 1,
+            7..=9 => 0,
+            _ => -1,
+        }
+    }
+}
+
+// knock-out, Hi-Lo with the 7 counted as a low card
+pub struct Ko;
+
+impl CountingSystem for Ko {
+    fn name(&self) -> &'static str {
+        "KO"
+    }
+
+    fn tag(&self, rank: Rank) -> i32 {
+        match rank.blackjack_value() {
+            2..=7 => 1,
+            8 | 9 => 0,
+            _ => -1,
+        }
+    }
+
+    fn initial_count(&self, decks: usize) -> i32 {
+        4 - 4 * decks as i32
+    }
+
+    fn is_balanced(&self) -> bool {
+        false
+    }
+}
+
+pub struct OmegaII;
+
+impl CountingSystem for OmegaII {
+    fn name(&self) -> &'static str {
+        "Omega II"
+    }
+
+    fn tag(&self, rank: Rank) -> i32 {
+        match rank.blackjack_value() {
+            2 | 3 | 7 => 1,
+            4..=6 => 2,
+            9 => -1,
+            10 => -2,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CountingKind {
+    HiLo,
+    Ko,
+    OmegaII,
+}
+
+impl CountingKind {
+    pub fn system(self) -> Box<dyn CountingSystem> {
+        match self {
+            CountingKind::HiLo => Box::new(HiLo),
+            CountingKind::Ko => Box::new(Ko),
+            CountingKind::OmegaII => Box::new(OmegaII),
+        }
+    }
+}
+
+impl Display for CountingKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            CountingKind::HiLo => write!(f, "hilo"),
+            CountingKind::Ko => write!(f, "ko"),
+            CountingKind::OmegaII => write!(f, "omega2"),
+        }
+    }
+}
+
+impl FromStr for CountingKind {
+    type Err = CardsError;
+
+    fn from_str(s: &str) -> Result<CountingKind, CardsError> {
+        let s = s.trim().to_lowercase().replace(['-', ' '], "");
+
+        match s.as_str() {
+            "hilo" => Ok(CountingKind::HiLo),
+            "ko" => Ok(CountingKind::Ko),
+            "omega2" | "omegaii" => Ok(CountingKind::OmegaII),
+            _ => Err(CardsError::InvalidInput(s)),
+        }
+    }
+}
+
+// the count at one moment
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Count {
+    pub system: &'static str,
+    pub running: i32,
+    // only for balanced systems
+    pub true_count: Option<f64>,
+}
+
+impl Display for Count {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} running count {}", self.system, self.running)?;
+
+        if let Some(x) = self.true_count {
+            write!(f, ", true count {:.1}", x)?;
+        }
+
+        Ok(())
+    }
+}
+
+// keeps the count of every card seen since the last shuffle, and how well
+// the player has kept up with it
+pub struct Counter {
+    system: Box<dyn CountingSystem>,
+    decks: usize,
+    running: i32,
+    quizzes: u32,
+    correct: u32,
+}
+
+impl Counter {
+    pub fn new(kind: CountingKind, decks: usize) -> Counter {
+        Counter::with_system(kind.system(), decks)
+    }
+
+    // for systems other than the built-in ones
+    pub fn with_system(system: Box<dyn CountingSystem>, decks: usize) -> Counter {
+        Counter {
+            running: system.initial_count(decks),
+            system,
+            decks,
+            quizzes: 0,
+            correct: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.running = self.system.initial_count(self.decks);
+    }
+
+    pub fn tag(&self, card: &Card) -> i32 {
+        self.system.tag(card.rank)
+    }
+
+    pub fn see(&mut self, card: &Card) {
+        self.running += self.tag(card);
+    }
+
+    pub fn see_all<'a>(&mut self, cards: impl IntoIterator<Item = &'a Card>) {
+        for card in cards {
+            self.see(card);
+        }
+    }
+
+    pub fn running(&self) -> i32 {
+        self.running
+    }
+
+    // cards_left is what has not been seen yet, estimated to the half deck
+    // like a player looking at the discard tray would
+    pub fn count(&self, running: i32, cards_left: usize) -> Count {
+        let decks_left = ((cards_left as f64 / 26.0).round() / 2.0).max(0.5);
+
+        Count {
+            system: self.system.name(),
+            running,
+            true_count: self
+                .system
+                .is_balanced()
+                .then(|| running as f64 / decks_left),
+        }
+    }
+
+    pub fn record_answer(&mut self, correct: bool) {
+        self.quizzes += 1;
+
+        if correct {
+            self.correct += 1;
+        }
+    }
+
+    // correct answers and questions asked
+    pub fn accuracy(&self) -> (u32, u32) {
+        (self.correct, self.quizzes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_count(kind: CountingKind, decks: usize) -> i32 {
+        let mut counter = Counter::new(kind, decks);
+
+        for _ in 0..decks {
+            counter.see_all(&Card::new_deck());
+        }
+
+        counter.running()
+    }
+
+    #[test]
+    fn balanced_systems_count_back_to_zero() {
+        assert_eq!(full_count(CountingKind::HiLo, 6), 0);
+        assert_eq!(full_count(CountingKind::OmegaII, 6), 0);
+    }
+
+    #[test]
+    fn ko_starts_below_zero() {
+        assert_eq!(Counter::new(CountingKind::Ko, 6).running(), -20);
+        assert_eq!(full_count(CountingKind::Ko, 6), 4);
+    }
+
+    #[test]
+    fn only_balanced_systems_have_a_true_count() {
+        let hilo = Counter::new(CountingKind::HiLo, 6);
+        let ko = Counter::new(CountingKind::Ko, 6);
+
+        assert_eq!(hilo.count(6, 104).true_count, Some(3.0));
+        assert_eq!(ko.count(6, 104).true_count, None);
+    }
+
+    #[test]
+    fn parses_system_names() {
+        assert_eq!("Hi-Lo".parse(), Ok(CountingKind::HiLo));
+        assert_eq!("omega ii".parse(), Ok(CountingKind::OmegaII));
+        assert!("zen".parse::<CountingKind>().is_err());
+    }
+}
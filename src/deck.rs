@@ -0,0 +1,215 @@
+use crate::card::Card;
+use crate::error::CardsError;
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
+
+// the top of the deck is the end of `cards`, so dealing is a pop
+#[derive(Clone)]
+pub struct Deck {
+    cards: Vec<Card>,
+    discards: Vec<Card>,
+    decks: usize,
+    penetration: f64,
+    rng: StdRng,
+}
+
+pub type Shoe = Deck;
+
+impl Deck {
+    pub fn new(rng: &mut impl Rng) -> Deck {
+        Deck::shoe(1, rng)
+    }
+
+    // shuffles use their own rng seeded from `rng`, so the same seed always
+    // gives the same order of cards
+    pub fn shoe(decks: usize, rng: &mut impl Rng) -> Deck {
+        let mut cards = Vec::new();
+
+        for _ in 0..decks {
+            cards.extend(Card::new_deck());
+        }
+
+        // new_deck is ordered from the top down
+        cards.reverse();
+
+        Deck {
+            cards,
+            discards: Vec::new(),
+            decks,
+            penetration: 1.0,
+            rng: StdRng::seed_from_u64(rng.gen()),
+        }
+    }
+
+    // a stacked deck, dealt in the order given
+    pub fn from_cards(mut cards: Vec<Card>, rng: &mut impl Rng) -> Deck {
+        cards.reverse();
+
+        Deck {
+            decks: cards.len().div_ceil(52),
+            cards,
+            discards: Vec::new(),
+            penetration: 1.0,
+            rng: StdRng::seed_from_u64(rng.gen()),
+        }
+    }
+
+    pub fn shuffle(&mut self) {
+        self.cards.shuffle(&mut self.rng);
+    }
+
+    // an empty deck is not reshuffled here, the game decides when to do that
+    pub fn deal(&mut self) -> Result<Card, CardsError> {
+        self.cards.pop().ok_or(CardsError::DeckExhausted)
+    }
+
+    pub fn deal_n(&mut self, count: usize) -> Result<Vec<Card>, CardsError> {
+        if self.cards.len() < count {
+            return Err(CardsError::DeckExhausted);
+        }
+
+        let mut cards = Vec::new();
+
+        for _ in 0..count {
+            cards.push(self.deal()?);
+        }
+
+        Ok(cards)
+    }
+
+    pub fn burn(&mut self) -> Result<(), CardsError> {
+        let card = self.deal()?;
+        self.discards.push(card);
+
+        Ok(())
+    }
+
+    pub fn cut(&mut self, at: usize) -> Result<(), CardsError> {
+        if at > self.cards.len() {
+            return Err(CardsError::InvalidArgument(format!(
+                "cannot cut {} cards from a deck of {}",
+                at,
+                self.cards.len()
+            )));
+        }
+
+        // the `at` cards on top move to the bottom
+        self.cards.rotate_right(at);
+
+        Ok(())
+    }
+
+    pub fn peek(&self) -> Option<&Card> {
+        self.cards.last()
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn size(&self) -> usize {
+        self.decks * 52
+    }
+
+    pub fn discard(&mut self, card: Card) {
+        self.discards.push(card);
+    }
+
+    pub fn discard_all(&mut self, cards: impl IntoIterator<Item = Card>) {
+        self.discards.extend(cards);
+    }
+
+    pub fn discards(&self) -> &[Card] {
+        &self.discards
+    }
+
+    pub fn set_penetration(&mut self, penetration: f64) -> Result<(), CardsError> {
+        if !(0.0..=1.0).contains(&penetration) {
+            return Err(CardsError::InvalidArgument(format!(
+                "penetration must be between 0 and 1, got {}",
+                penetration
+            )));
+        }
+
+        self.penetration = penetration;
+        Ok(())
+    }
+
+    pub fn cut_card(&self) -> usize {
+        self.size() - (self.size() as f64 * self.penetration).round() as usize
+    }
+
+    pub fn needs_shuffle(&self) -> bool {
+        self.cards.len() <= self.cut_card()
+    }
+
+    pub fn reshuffle(&mut self) {
+        self.cards.append(&mut self.discards);
+        self.shuffle();
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Deck {
+        Deck::new(&mut thread_rng())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::parse_hand;
+
+    fn stacked(cards: &str) -> Deck {
+        Deck::from_cards(parse_hand(cards).unwrap(), &mut StdRng::seed_from_u64(1))
+    }
+
+    #[test]
+    fn deals_a_stacked_deck_in_order() {
+        let mut deck = stacked("as 2h 3d");
+
+        assert_eq!(deck.peek(), Some(&"as".parse().unwrap()));
+        assert_eq!(deck.deal_n(3).unwrap(), parse_hand("as 2h 3d").unwrap());
+        assert_eq!(deck.size(), 52);
+    }
+
+    #[test]
+    fn burns_and_cuts() {
+        let mut deck = stacked("as 2h 3d 4c");
+
+        deck.burn().unwrap();
+        assert_eq!(deck.discards(), parse_hand("as").unwrap().as_slice());
+
+        // 2h moves to the bottom
+        deck.cut(1).unwrap();
+        assert_eq!(deck.deal_n(3).unwrap(), parse_hand("3d 4c 2h").unwrap());
+        assert!(deck.cut(1).is_err());
+    }
+
+    #[test]
+    fn refuses_to_deal_more_than_it_has() {
+        let mut deck = stacked("as 2h");
+
+        assert_eq!(deck.deal_n(3), Err(CardsError::DeckExhausted));
+        assert_eq!(deck.remaining(), 2);
+    }
+
+    #[test]
+    fn leaves_the_reshuffle_to_the_game() {
+        let mut deck = stacked("as");
+
+        deck.burn().unwrap();
+        assert_eq!(deck.deal(), Err(CardsError::DeckExhausted));
+
+        deck.reshuffle();
+        assert_eq!(deck.deal(), Ok("as".parse().unwrap()));
+    }
+
+    #[test]
+    fn places_the_cut_card() {
+        let mut deck = Deck::shoe(2, &mut StdRng::seed_from_u64(1));
+
+        deck.set_penetration(0.75).unwrap();
+        assert_eq!(deck.cut_card(), 26);
+        assert!(deck.set_penetration(1.5).is_err());
+    }
+}
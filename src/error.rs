@@ -0,0 +1,62 @@
+use std::{
+    error::Error,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::card::ParseCardError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CardsError {
+    InvalidCard(ParseCardError),
+    DeckExhausted,
+    InvalidPlayerCount {
+        given: usize,
+        min: usize,
+        max: usize,
+    },
+    UnknownPlayer(i32),
+    InvalidInput(String),
+    InvalidOption(i32),
+    InvalidArgument(String),
+    // the player asked to quit, or there is no more input
+    Quit,
+    Terminal(String),
+    // a simulation thread panicked
+    ThreadFailed,
+}
+
+impl Display for CardsError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            CardsError::InvalidCard(x) => write!(f, "Invalid card: {}", x),
+            CardsError::DeckExhausted => write!(f, "Not enough cards"),
+            CardsError::InvalidPlayerCount { given, min, max } => write!(
+                f,
+                "This game needs between {} and {} players, got {}",
+                min, max, given
+            ),
+            CardsError::UnknownPlayer(x) => write!(f, "There is no player with id {}", x),
+            CardsError::InvalidInput(x) => write!(f, "Invalid input: \"{}\"", x),
+            CardsError::InvalidOption(x) => write!(f, "{} is not one of the options", x),
+            CardsError::InvalidArgument(x) => write!(f, "Invalid argument: {}", x),
+            CardsError::Quit => write!(f, "Quit"),
+            CardsError::Terminal(x) => write!(f, "Terminal error: {}", x),
+            CardsError::ThreadFailed => write!(f, "A simulation thread stopped unexpectedly"),
+        }
+    }
+}
+
+impl Error for CardsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CardsError::InvalidCard(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseCardError> for CardsError {
+    fn from(error: ParseCardError) -> CardsError {
+        CardsError::InvalidCard(error)
+    }
+}
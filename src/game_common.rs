@@ -1,26 +1,64 @@
+use crate::error::CardsError;
 use crate::player::Player;
-use crate::ui::*;
+use crate::ui::UserInterface;
+
+#[derive(Clone, Debug)]
+pub struct Standing {
+    pub player_id: i32,
+    pub name: String,
+    pub score: i32,
+    // knocked out of the game, or busted
+    pub out: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum RoundOutcome {
+    Continue,
+    GameOver {
+        winners: Vec<i32>,
+        standings: Vec<Standing>,
+    },
+}
 
 pub trait Game {
-    fn round(&mut self);
+    fn round(&mut self) -> Result<RoundOutcome, CardsError>;
     fn get_players(&self) -> &Vec<Player>;
+    // best first, players who are out last
+    fn standings(&self) -> Vec<Standing>;
+
+    fn winners(&self, standings: &[Standing]) -> Vec<i32> {
+        leaders(standings)
+    }
+}
+
+// everyone still in the game who shares the best score
+pub fn leaders(standings: &[Standing]) -> Vec<i32> {
+    standings
+        .iter()
+        .filter(|x| !x.out && x.score == standings[0].score)
+        .map(|x| x.player_id)
+        .collect()
 }
 
-pub fn get_player_names(player_ct: i32) -> Vec<String> {
-    console_clear!();
-    header_start!();
+pub fn check_player_count(given: usize, min: usize, max: usize) -> Result<(), CardsError> {
+    if given < min || given > max {
+        return Err(CardsError::InvalidPlayerCount { given, min, max });
+    }
+
+    Ok(())
+}
+
+pub fn get_player_names(
+    ui: &mut dyn UserInterface,
+    player_ct: i32,
+) -> Result<Vec<String>, CardsError> {
+    ui.clear();
 
     let mut names = Vec::new();
 
     for i in 0..player_ct {
-        let name = prompt!("Enter Player {}'s name: ", i + 1)
-            .trim()
-            .to_string();
-
-        names.push(name);
+        names.push(ui.ask_text(&format!("Enter Player {}'s name: ", i + 1))?);
     }
 
-    console_clear!();
-
-    names
+    Ok(names)
 }
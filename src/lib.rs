@@ -0,0 +1,17 @@
+pub mod blackjack;
+pub mod card;
+pub mod controller;
+pub mod counting;
+pub mod deck;
+pub mod error;
+pub mod game_common;
+pub mod player;
+pub mod render;
+pub mod simulation;
+pub mod strategy;
+#[cfg(feature = "terminal")]
+pub mod terminal;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod ui;
+pub mod war;
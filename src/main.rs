@@ -1,56 +1,495 @@
-use crate::blackjack::BlackJack;
-use crate::game_common::{get_player_names, Game};
-use crate::ui::*;
-use crate::war::War;
-
-pub mod blackjack;
-pub mod card;
-pub mod game_common;
-pub mod player;
-pub mod ui;
-pub mod war;
+use rand::rngs::StdRng;
+use rand::{random, SeedableRng};
+
+use cards::blackjack::{BlackJack, BlackJackRules};
+use cards::controller::ControllerKind;
+use cards::error::CardsError;
+use cards::game_common::{check_player_count, get_player_names, Game, RoundOutcome, Standing};
+use cards::render::{Renderer, Style};
+use cards::simulation::{write_csv, Simulation, WarSimulation};
+use cards::terminal::{console_clear, TerminalUi};
+use cards::ui::{Option, UserInterface};
+use cards::war::War;
+
+const GAMES: [(&str, &str); 2] = [("war", "War"), ("blackjack", "BlackJack")];
+
+const USAGE: &str = "Usage: war-rust [options]
+
+Options:
+    --game <name>         game to play, see --list-games
+    --players <a,b,...>   comma separated player names, add :cpu, :cpu<n> or
+                          :basic to a name for a computer player
+    --seed <number>       seed for shuffling, to replay the same deal
+    --rounds <number>     stop each game after this many rounds
+    --no-dealer           blackjack between the players, without a dealer
+    --hit-soft-17         the blackjack dealer hits a soft 17
+    --manual-aces         blackjack house rule: pick 1 or 11 for every ace
+    --bankroll <chips>    chips every blackjack player starts with
+    --min-bet <chips>     the blackjack table minimum
+    --max-bet <chips>     the blackjack table maximum
+    --blackjack-pays <x>  3:2 or 6:5 for a natural blackjack
+    --double-on <a-b>     totals a blackjack player may double down on
+    --max-hands <number>  most hands a blackjack player can split into
+    --no-das              no doubling down after a split
+    --resplit-aces        split aces can be split again
+    --hit-split-aces      split aces can take more than one card
+    --no-surrender        no late surrender
+    --no-insurance        no insurance when the dealer shows an ace
+    --no-peek             the dealer doesn't check for blackjack early, and
+                          nobody may surrender to an ace or ten
+    --decks <number>      decks in the blackjack shoe
+    --penetration <x>     share of the shoe dealt before a reshuffle, 0 to 1
+    --no-hints            hide the basic strategy hint from human players
+    --count <system>      practise card counting with hilo, ko or omega2
+    --quiz-every <hands>  hands between count quizzes, 0 for none
+    --simulate <number>   play this many blackjack hands with a basic strategy
+                          bot, or games of war with --game war, without any
+                          output and report statistics
+    --threads <number>    threads for --simulate, all cores by default
+    --player-count <a,..> player counts to simulate war with, 2 by default
+    --csv <path>          write every simulated game of war to a CSV file
+    --cards <style>       plain, symbol, glyph or art, symbol by default
+    --tui                 full-screen interface (needs the tui feature)
+    --list-games          list the games and exit
+    -h, --help            show this message and exit";
+
+#[derive(Default)]
+struct Args {
+    game: std::option::Option<i32>,
+    players: std::option::Option<Vec<(String, ControllerKind)>>,
+    seed: std::option::Option<u64>,
+    rounds: std::option::Option<u32>,
+    rules: BlackJackRules,
+    simulate: std::option::Option<u64>,
+    threads: std::option::Option<usize>,
+    player_counts: std::option::Option<Vec<usize>>,
+    csv: std::option::Option<String>,
+    cards: std::option::Option<Style>,
+    tui: bool,
+    list_games: bool,
+    help: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Args, CardsError> {
+        let mut parsed = Args::default();
+
+        while let Some(arg) = args.next() {
+            // both "--game war" and "--game=war"
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if arg.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CardsError::InvalidArgument(format!("{} needs a value", flag)))
+            };
+
+            match flag.as_str() {
+                "--game" => parsed.game = Some(parse_game(&value()?)?),
+                "--players" => {
+                    parsed.players = Some(
+                        value()?
+                            .split(',')
+                            .filter(|x| !x.trim().is_empty())
+                            .map(parse_player)
+                            .collect::<Result<_, _>>()?,
+                    )
+                }
+                "--seed" => parsed.seed = Some(parse_number(&flag, &value()?)?),
+                "--rounds" => parsed.rounds = Some(parse_number(&flag, &value()?)?),
+                "--no-dealer" => parsed.rules.dealer = false,
+                "--hit-soft-17" => parsed.rules.dealer_hits_soft_17 = true,
+                "--manual-aces" => parsed.rules.manual_aces = true,
+                "--bankroll" => parsed.rules.starting_bankroll = parse_number(&flag, &value()?)?,
+                "--min-bet" => parsed.rules.table_min = parse_number(&flag, &value()?)?,
+                "--max-bet" => parsed.rules.table_max = parse_number(&flag, &value()?)?,
+                "--double-on" => {
+                    let totals = value()?;
+                    let (low, high) = totals.split_once('-').unwrap_or((&totals, &totals));
+                    parsed.rules.double_totals =
+                        parse_number(&flag, low)?..=parse_number(&flag, high)?;
+                }
+                "--max-hands" => parsed.rules.max_hands = parse_number(&flag, &value()?)?,
+                "--no-das" => parsed.rules.double_after_split = false,
+                "--resplit-aces" => parsed.rules.resplit_aces = true,
+                "--hit-split-aces" => parsed.rules.hit_split_aces = true,
+                "--no-surrender" => parsed.rules.surrender = false,
+                "--no-insurance" => parsed.rules.insurance = false,
+                "--no-peek" => parsed.rules.dealer_peeks = false,
+                "--decks" => parsed.rules.decks = parse_number(&flag, &value()?)?,
+                "--penetration" => parsed.rules.penetration = parse_number(&flag, &value()?)?,
+                "--no-hints" => parsed.rules.hints = false,
+                "--quiz-every" => parsed.rules.quiz_every = parse_number(&flag, &value()?)?,
+                "--blackjack-pays" => {
+                    let pays = value()?;
+                    parsed.rules.blackjack_pays = pays.parse().map_err(|_| {
+                        CardsError::InvalidArgument(format!("\"{}\" is not 3:2 or 6:5", pays))
+                    })?
+                }
+                "--count" => {
+                    let system = value()?;
+                    parsed.rules.counting = Some(system.parse().map_err(|_| {
+                        CardsError::InvalidArgument(format!(
+                            "unknown counting system \"{}\", use hilo, ko or omega2",
+                            system
+                        ))
+                    })?)
+                }
+                "--simulate" => parsed.simulate = Some(parse_number(&flag, &value()?)?),
+                "--threads" => parsed.threads = Some(parse_number(&flag, &value()?)?),
+                "--player-count" => {
+                    parsed.player_counts = Some(
+                        value()?
+                            .split(',')
+                            .map(|x| parse_number(&flag, x.trim()))
+                            .collect::<Result<Vec<usize>, CardsError>>()?,
+                    )
+                }
+                "--csv" => parsed.csv = Some(value()?),
+                "--cards" => {
+                    let style = value()?;
+                    parsed.cards = Some(style.parse().map_err(|_| {
+                        CardsError::InvalidArgument(format!(
+                            "unknown card style \"{}\", use plain, symbol, glyph or art",
+                            style
+                        ))
+                    })?)
+                }
+                "--tui" => parsed.tui = true,
+                "--list-games" => parsed.list_games = true,
+                "-h" | "--help" => parsed.help = true,
+                _ => {
+                    return Err(CardsError::InvalidArgument(format!(
+                        "unknown option \"{}\", see --help",
+                        arg
+                    )))
+                }
+            }
+        }
+
+        // war games are the only ones saved
+        if parsed.csv.is_some() && (parsed.game != Some(1) || parsed.simulate.is_none()) {
+            return Err(CardsError::InvalidArgument(
+                "--csv only works with --game war --simulate".to_string(),
+            ));
+        }
+
+        Ok(parsed)
+    }
+}
+
+fn parse_game(name: &str) -> Result<i32, CardsError> {
+    GAMES
+        .iter()
+        .position(|(id, _)| id.eq_ignore_ascii_case(name))
+        .map(|x| x as i32 + 1)
+        .ok_or_else(|| {
+            CardsError::InvalidArgument(format!("unknown game \"{}\", see --list-games", name))
+        })
+}
+
+// "alice" is a human, "bob:basic" a computer player
+fn parse_player(player: &str) -> Result<(String, ControllerKind), CardsError> {
+    match player.split_once(':') {
+        Some((name, kind)) => {
+            let kind = kind.parse().map_err(|_| {
+                CardsError::InvalidArgument(format!("unknown player kind \"{}\"", kind))
+            })?;
+
+            Ok((name.trim().to_string(), kind))
+        }
+        None => Ok((player.trim().to_string(), ControllerKind::Human)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, CardsError> {
+    value.parse().map_err(|_| {
+        CardsError::InvalidArgument(format!("\"{}\" is not a valid number for {}", value, flag))
+    })
+}
 
 fn main() {
-    console_clear!();
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(x) => x,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(2);
+        }
+    };
 
-    let game_id = prompt_options!(
-        "Which game to play?",
-        &[
-            Option {
-                name: "War",
-                value: 1,
-            },
-            Option {
-                name: "BlackJack",
-                value: 2,
-            },
-        ]
-    );
+    if args.help {
+        println!("{}", USAGE);
+        return;
+    }
+
+    if args.list_games {
+        for (id, name) in GAMES {
+            println!("{:<12}{}", id, name);
+        }
+
+        return;
+    }
+
+    if args.simulate.is_some() {
+        if let Err(error) = simulate(args) {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    match run(args) {
+        Ok(()) | Err(CardsError::Quit) => console_clear!(),
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+// reports go to stdout, nothing is shown while the games are played
+fn simulate(args: Args) -> Result<(), CardsError> {
+    let count = args.simulate.unwrap_or(0);
+    let seed = args.seed.unwrap_or_else(random);
+
+    // war is the first game
+    if args.game != Some(1) {
+        let mut simulation = Simulation::new(args.rules, count, seed);
+        simulation.threads = args.threads.unwrap_or(simulation.threads);
+
+        print!("{}", simulation.run()?);
+        return Ok(());
+    }
+
+    let mut reports = Vec::new();
+
+    for players in args.player_counts.unwrap_or(vec![2]) {
+        check_player_count(players, 2, 4)?;
+
+        let mut simulation = WarSimulation::new(players, count, seed);
+        simulation.threads = args.threads.unwrap_or(simulation.threads);
+        simulation.max_rounds = args.rounds.unwrap_or(simulation.max_rounds);
+
+        let report = simulation.run()?;
+
+        if !reports.is_empty() {
+            println!();
+        }
+
+        print!("{}", report);
+        reports.push(report);
+    }
+
+    if let Some(path) = args.csv {
+        std::fs::File::create(&path)
+            .and_then(|mut x| write_csv(&reports, &mut x))
+            .map_err(|x| CardsError::InvalidArgument(format!("cannot write {}: {}", path, x)))?;
+    }
+
+    Ok(())
+}
+
+fn run(args: Args) -> Result<(), CardsError> {
+    if let Some(players) = &args.players {
+        check_player_count(players.len(), 1, 4)?;
+    }
+
+    let seed = args.seed.unwrap_or_else(random);
+    let mut ui = new_ui(args.tui, args.cards)?;
 
     console_clear!();
-    let player_ct = prompt_headers!("How many players?").trim().parse().unwrap();
-    let player_names = get_player_names(player_ct);
 
-    let mut game: Box<dyn Game> = match game_id {
-        1 => Box::new(War::new(player_names)),
-        2 => Box::new(BlackJack::new(player_names)),
-        _ => panic!("E_INVALID_GAME"),
+    let game_id = match args.game {
+        Some(x) => x,
+        None => {
+            let options = GAMES
+                .iter()
+                .enumerate()
+                .map(|(i, (_, name))| Option {
+                    name,
+                    value: i as i32 + 1,
+                })
+                .collect::<Vec<Option>>();
+
+            ui.choose_option("Which game to play?", &options)?
+        }
     };
 
-    console_clear!();
-    header_start!();
-    println!("Players:");
+    let rules = args.rules;
+
+    // a game against the dealer can be played alone
+    let min_players = if game_id == 2 && rules.dealer { 1 } else { 2 };
+
+    let players = match args.players {
+        Some(x) => x,
+        None => ask_players(&mut *ui, min_players)?,
+    };
+    let player_ct = players.len();
+
+    // every game gets its own seed, so any of them can be replayed with --seed
+    for game_seed in (0..).map(|x| seed.wrapping_add(x)) {
+        let mut rng = StdRng::seed_from_u64(game_seed);
+
+        ui.clear();
+        ui.show_message("Players:");
+
+        for (name, kind) in &players {
+            if kind.is_human() {
+                ui.show_message(name);
+            } else {
+                ui.show_message(&format!("{} ({})", name, kind));
+            }
+        }
+
+        ui.show_message(&format!("\n{} total", player_ct));
+        ui.show_message(&format!("Seed: {}", game_seed));
+        ui.pause()?;
+
+        let mut game = new_game(
+            game_id,
+            players.clone(),
+            &rules,
+            args.rounds,
+            &mut rng,
+            &mut *ui,
+        )?;
+
+        let mut rounds = 0;
+
+        let (winners, standings) = loop {
+            if let RoundOutcome::GameOver { winners, standings } = game.round()? {
+                break (winners, standings);
+            }
+
+            rounds += 1;
+
+            if args.rounds.is_some_and(|x| rounds >= x) {
+                let standings = game.standings();
+                break (game.winners(&standings), standings);
+            }
+        };
+
+        drop(game);
+        print_results(&mut *ui, &winners, &standings);
+
+        ui.show_message("");
+
+        if !ui.ask_yes_no("Play again?", Some(true))? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn ask_players(
+    ui: &mut dyn UserInterface,
+    min_players: i32,
+) -> Result<Vec<(String, ControllerKind)>, CardsError> {
+    ui.clear();
+    let human_ct = ui.ask_number("How many human players? ", 1..=4, None)?;
+
+    // computer players fill up the table to the minimum
+    let min = (min_players - human_ct).max(0);
+    let computer_ct = if human_ct < 4 {
+        ui.ask_number("How many computer players? ", min..=4 - human_ct, Some(min))?
+    } else {
+        0
+    };
+
+    let mut players = get_player_names(ui, human_ct)?
+        .into_iter()
+        .map(|x| (x, ControllerKind::Human))
+        .collect::<Vec<(String, ControllerKind)>>();
+
+    players
+        .extend((1..=computer_ct).map(|i| (format!("CPU {}", i), ControllerKind::BasicStrategy)));
+
+    Ok(players)
+}
+
+#[cfg(feature = "tui")]
+fn new_ui(
+    tui: bool,
+    style: std::option::Option<Style>,
+) -> Result<Box<dyn UserInterface>, CardsError> {
+    if tui {
+        let style = style.unwrap_or(Style::Symbol);
+        return Ok(Box::new(cards::tui::TuiUi::with_style(style)?));
+    }
+
+    Ok(Box::new(terminal_ui(style)))
+}
+
+#[cfg(not(feature = "tui"))]
+fn new_ui(
+    tui: bool,
+    style: std::option::Option<Style>,
+) -> Result<Box<dyn UserInterface>, CardsError> {
+    if tui {
+        return Err(CardsError::InvalidArgument(
+            "--tui needs the tui feature".to_string(),
+        ));
+    }
+
+    Ok(Box::new(terminal_ui(style)))
+}
+
+// a style that was asked for is kept even when the output is not a terminal
+fn terminal_ui(style: std::option::Option<Style>) -> TerminalUi {
+    match style {
+        Some(style) => TerminalUi::new(Renderer::colored(style)),
+        None => TerminalUi::default(),
+    }
+}
+
+fn new_game<'a>(
+    game_id: i32,
+    players: Vec<(String, ControllerKind)>,
+    rules: &BlackJackRules,
+    max_rounds: std::option::Option<u32>,
+    rng: &mut StdRng,
+    ui: &'a mut dyn UserInterface,
+) -> Result<Box<dyn Game + 'a>, CardsError> {
+    Ok(match game_id {
+        1 => {
+            let mut war = War::with_controllers(players, rng, ui)?;
+            war.max_rounds = max_rounds;
+            Box::new(war)
+        }
+        2 => Box::new(BlackJack::with_rules(players, rules.clone(), rng, ui)?),
+        _ => return Err(CardsError::InvalidOption(game_id)),
+    })
+}
+
+fn print_results(ui: &mut dyn UserInterface, winners: &[i32], standings: &[Standing]) {
+    ui.clear();
+
+    if winners.is_empty() {
+        ui.show_message("There were no winners");
+    }
 
-    for player in game.get_players() {
-        println!("{}", player.name);
+    for standing in standings.iter().filter(|x| winners.contains(&x.player_id)) {
+        ui.show_message(&format!("{} wins the game!", standing.name));
     }
 
-    println!("\n{} total", player_ct);
-    header_end!();
-    prompt!("Press enter to continue...");
+    ui.show_message("\nStandings:");
 
-    loop {
-        console_clear!();
-        game.round();
+    for (i, standing) in standings.iter().enumerate() {
+        let out = if standing.out { " (out)" } else { "" };
+        ui.show_message(&format!(
+            "{}. {}: {}{}",
+            i + 1,
+            standing.name,
+            standing.score,
+            out
+        ));
     }
 }
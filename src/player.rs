@@ -1,14 +1,15 @@
-use std::error::Error;
-
-use rand::{thread_rng, Rng};
-
 use crate::card::Card;
+use crate::controller::ControllerKind;
+use crate::deck::Deck;
+use crate::error::CardsError;
 
 #[derive(Clone)]
 pub struct Player {
     pub deck: Vec<Card>,
     pub name: String,
     pub id: i32,
+    pub controller: ControllerKind,
+    pub bankroll: i32,
 }
 
 #[derive(Clone)]
@@ -18,20 +19,16 @@ pub struct PlayerCard {
 }
 
 impl Player {
-    pub fn new(name: String, id: i32, from_deck: &mut Vec<Card>, card_count: i32) -> Player {
-        let mut deck = Vec::new();
+    pub fn new(name: String, id: i32, from_deck: &mut Deck, card_count: i32) -> Result<Player, CardsError> {
+        let deck = from_deck.deal_n(card_count as usize)?;
 
-        for _ in 0..card_count {
-			let index = thread_rng().gen_range(0..from_deck.len());
-            deck.push(from_deck[index].clone());
-            from_deck.remove(index);
-        }
-
-        Player {
+        Ok(Player {
             id,
             deck,
-            name
-        }
+            name,
+            controller: ControllerKind::Human,
+            bankroll: 0,
+        })
     }
 
 	pub fn copy(&self) -> Player {
@@ -39,29 +36,31 @@ impl Player {
 			id: self.id,
 			deck: self.deck.clone(),
 			name: self.name.clone(),
+			controller: self.controller,
+			bankroll: self.bankroll,
 		}
 	}
 
-	pub fn draw(&mut self) -> Result<PlayerCard, Box<dyn Error>> {
+	pub fn draw(&mut self) -> Result<PlayerCard, CardsError> {
 		if self.deck.is_empty() {
-			return Err("Not enough cards".into())
+			return Err(CardsError::DeckExhausted)
 		}
 
-		let card = self.deck[0].clone();
+		let card = self.deck[0];
 		self.deck.remove(0);
 
 		Ok(PlayerCard { card, player_id: self.id })
 	}
 
-	pub fn draw_all(&mut self, count: i32) -> Result<Vec<PlayerCard>, Box<dyn Error>> {
+	pub fn draw_all(&mut self, count: i32) -> Result<Vec<PlayerCard>, CardsError> {
 		let mut cards = Vec::new();
 
 		if self.deck.len() < count as usize {
-			return Err("Not enough cards".into())
+			return Err(CardsError::DeckExhausted)
 		}
 
 		for _ in 0..count {
-			let card = self.draw().unwrap();
+			let card = self.draw()?;
 			cards.push(card);
 		}
 
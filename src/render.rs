@@ -0,0 +1,146 @@
+use std::io::{stdout, IsTerminal};
+use std::str::FromStr;
+
+use crate::card::Card;
+use crate::error::CardsError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Style {
+    // "Queen of Hearts"
+    Plain,
+    // "Q♥"
+    Symbol,
+    // the unicode playing card glyphs
+    Glyph,
+    // boxes drawn over several lines, laid side by side in a hand
+    Art,
+}
+
+impl FromStr for Style {
+    type Err = CardsError;
+
+    fn from_str(s: &str) -> Result<Style, CardsError> {
+        match s.trim().to_lowercase().as_str() {
+            "plain" => Ok(Style::Plain),
+            "symbol" => Ok(Style::Symbol),
+            "glyph" => Ok(Style::Glyph),
+            "art" => Ok(Style::Art),
+            _ => Err(CardsError::InvalidInput(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Renderer {
+    pub style: Style,
+    pub color: bool,
+}
+
+const RED: &str = "\x1B[31;47m";
+const BLACK: &str = "\x1B[30;47m";
+const RESET: &str = "\x1B[0m";
+
+const CARD_BACK: char = '\u{1F0A0}';
+
+impl Renderer {
+    pub fn new(style: Style, color: bool) -> Renderer {
+        Renderer { style, color }
+    }
+
+    // falls back to plain text when stdout is not a terminal
+    pub fn detect(style: Style) -> Renderer {
+        if !stdout().is_terminal() {
+            return Renderer::new(Style::Plain, false);
+        }
+
+        Renderer::colored(style)
+    }
+
+    // keeps the style, colors only on a terminal without NO_COLOR
+    pub fn colored(style: Style) -> Renderer {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|x| !x.is_empty());
+        Renderer::new(style, stdout().is_terminal() && !no_color)
+    }
+
+    fn paint(&self, card: &Card, text: &str) -> String {
+        if !self.color {
+            return text.to_string();
+        }
+
+        let color = if card.suit.is_red() { RED } else { BLACK };
+        format!("{}{}{}", color, text, RESET)
+    }
+
+    // always a single line, art cards are shown with their symbol
+    pub fn card(&self, card: &Card) -> String {
+        match self.style {
+            Style::Plain if card.visible => card.to_string(),
+            Style::Plain => "a face-down card".to_string(),
+            Style::Glyph if card.visible => self.paint(card, &card.glyph().to_string()),
+            Style::Glyph => CARD_BACK.to_string(),
+            Style::Symbol | Style::Art if card.visible => {
+                let text = format!("{}{}", card.rank.short_name(), card.suit.symbol());
+                self.paint(card, &text)
+            }
+            Style::Symbol | Style::Art => "??".to_string(),
+        }
+    }
+
+    pub fn hand(&self, cards: &[Card]) -> String {
+        match self.style {
+            Style::Plain => cards
+                .iter()
+                .map(|x| self.card(x))
+                .collect::<Vec<String>>()
+                .join(", "),
+            Style::Symbol | Style::Glyph => cards
+                .iter()
+                .map(|x| self.card(x))
+                .collect::<Vec<String>>()
+                .join(" "),
+            Style::Art => {
+                let boxes = cards.iter().map(|x| self.art(x)).collect::<Vec<_>>();
+
+                (0..5)
+                    .map(|line| {
+                        boxes
+                            .iter()
+                            .map(|x| x[line].as_str())
+                            .collect::<Vec<&str>>()
+                            .join(" ")
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n")
+            }
+        }
+    }
+
+    fn art(&self, card: &Card) -> [String; 5] {
+        if !card.visible {
+            return [
+                "+-----+".to_string(),
+                "|#####|".to_string(),
+                "|#####|".to_string(),
+                "|#####|".to_string(),
+                "+-----+".to_string(),
+            ];
+        }
+
+        let rank = card.rank.short_name();
+        let suit = card.suit.symbol().to_string();
+
+        [
+            "+-----+".to_string(),
+            format!("|{}|", self.paint(card, &format!("{:<5}", rank))),
+            format!("|{}|", self.paint(card, &format!("  {}  ", suit))),
+            format!("|{}|", self.paint(card, &format!("{:>5}", rank))),
+            "+-----+".to_string(),
+        ]
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer::detect(Style::Symbol)
+    }
+}
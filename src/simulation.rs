@@ -0,0 +1,447 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+use std::thread;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::blackjack::{hand_value, BlackJack, BlackJackRules, Outcome};
+use crate::controller::ControllerKind;
+use crate::error::CardsError;
+use crate::game_common::{Game, RoundOutcome};
+use crate::ui::HeadlessUi;
+use crate::war::{War, WarEnding};
+
+// hands are played in blocks with their own seed and a fresh shoe, so the
+// results don't depend on the threads
+const BLOCK_HANDS: u64 = 10_000;
+
+// every block is played at its own table with one computer player
+#[derive(Clone, Debug)]
+pub struct Simulation {
+    pub rules: BlackJackRules,
+    pub controller: ControllerKind,
+    pub hands: u64,
+    pub threads: usize,
+    pub seed: u64,
+}
+
+impl Simulation {
+    pub fn new(rules: BlackJackRules, hands: u64, seed: u64) -> Simulation {
+        Simulation {
+            rules,
+            controller: ControllerKind::BasicStrategy,
+            hands,
+            threads: thread::available_parallelism().map_or(1, |x| x.get()),
+            seed,
+        }
+    }
+
+    pub fn run(&self) -> Result<SimulationReport, CardsError> {
+        if !self.rules.dealer {
+            return Err(CardsError::InvalidArgument(
+                "simulations need a dealer".to_string(),
+            ));
+        }
+
+        let threads = self.threads.max(1) as u64;
+        let blocks = self.hands.div_ceil(BLOCK_HANDS);
+
+        let mut parts = thread::scope(|scope| {
+            let workers = (0..threads)
+                .map(|first| {
+                    scope.spawn(move || {
+                        (first..blocks)
+                            .step_by(threads as usize)
+                            .map(|block| {
+                                let hands = BLOCK_HANDS.min(self.hands - block * BLOCK_HANDS);
+                                let seed = self.seed.wrapping_add(block);
+                                Ok((block, self.run_table(hands, seed)?))
+                            })
+                            .collect::<Result<Vec<(u64, SimulationReport)>, CardsError>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut parts = Vec::new();
+
+            for worker in workers {
+                parts.extend(worker.join().map_err(|_| CardsError::ThreadFailed)??);
+            }
+
+            Ok::<_, CardsError>(parts)
+        })?;
+
+        // merged in the same order every time, so the sums round the same way
+        parts.sort_by_key(|(block, _)| *block);
+
+        let mut report = SimulationReport::new(&self.rules);
+
+        for (_, part) in &parts {
+            report.merge(part);
+        }
+
+        Ok(report)
+    }
+
+    fn run_table(&self, hands: u64, seed: u64) -> Result<SimulationReport, CardsError> {
+        let mut rules = self.rules.clone();
+        // a fixed bet that every payout divides evenly, and chips that never
+        // run out
+        rules.table_min = 100;
+        rules.table_max = 100;
+        rules.starting_bankroll = i32::MAX / 2;
+        rules.counting = None;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let seats = vec![("Bot".to_string(), self.controller)];
+        let mut game = BlackJack::with_rules(seats, rules, &mut rng, HeadlessUi)?;
+        let mut report = SimulationReport::new(&self.rules);
+
+        for _ in 0..hands {
+            let before = game.get_players()[0].bankroll;
+
+            if let RoundOutcome::GameOver { .. } = game.round()? {
+                break;
+            }
+
+            let after = game.get_players()[0].bankroll;
+            report.add_round(f64::from(after - before) / 100.0);
+
+            let last = game.last_hand();
+
+            if hand_value(&last.dealer).total > 21 {
+                report.dealer_busts += 1;
+            }
+
+            for hand in last.hands.values().flatten() {
+                report.hands += 1;
+
+                if hand.value(self.rules.manual_aces).total > 21 {
+                    report.player_busts += 1;
+                }
+
+                if let Some(outcome) = hand.outcome {
+                    *report.outcomes.entry(outcome).or_insert(0) += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SimulationReport {
+    pub rules: String,
+    pub rounds: u64,
+    // hands after splits
+    pub hands: u64,
+    // won or lost, counted in initial bets
+    pub net: f64,
+    net_squared: f64,
+    pub player_busts: u64,
+    pub dealer_busts: u64,
+    pub outcomes: HashMap<Outcome, u64>,
+}
+
+impl SimulationReport {
+    pub fn new(rules: &BlackJackRules) -> SimulationReport {
+        SimulationReport {
+            rules: rules.summary(),
+            ..SimulationReport::default()
+        }
+    }
+
+    pub fn add_round(&mut self, net: f64) {
+        self.rounds += 1;
+        self.net += net;
+        self.net_squared += net * net;
+    }
+
+    pub fn merge(&mut self, other: &SimulationReport) {
+        self.rounds += other.rounds;
+        self.hands += other.hands;
+        self.net += other.net;
+        self.net_squared += other.net_squared;
+        self.player_busts += other.player_busts;
+        self.dealer_busts += other.dealer_busts;
+
+        for (outcome, count) in &other.outcomes {
+            *self.outcomes.entry(*outcome).or_insert(0) += count;
+        }
+    }
+
+    // what the house keeps of every initial bet, positive favours the house
+    pub fn house_edge(&self) -> f64 {
+        -self.net / self.rounds.max(1) as f64
+    }
+
+    // of the result of one round, in initial bets squared
+    pub fn variance(&self) -> f64 {
+        let rounds = self.rounds.max(1) as f64;
+        let mean = self.net / rounds;
+
+        self.net_squared / rounds - mean * mean
+    }
+
+    pub fn player_bust_rate(&self) -> f64 {
+        self.player_busts as f64 / self.hands.max(1) as f64
+    }
+
+    pub fn dealer_bust_rate(&self) -> f64 {
+        self.dealer_busts as f64 / self.rounds.max(1) as f64
+    }
+
+    pub fn outcome_rate(&self, outcome: Outcome) -> f64 {
+        *self.outcomes.get(&outcome).unwrap_or(&0) as f64 / self.hands.max(1) as f64
+    }
+}
+
+impl Display for SimulationReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "Rules: {}", self.rules)?;
+        writeln!(f, "Rounds: {}, hands: {}", self.rounds, self.hands)?;
+        writeln!(f, "House edge: {:.3}%", self.house_edge() * 100.0)?;
+        writeln!(
+            f,
+            "Variance: {:.3} (standard deviation {:.3}) per round",
+            self.variance(),
+            self.variance().sqrt()
+        )?;
+        writeln!(
+            f,
+            "Player bust rate: {:.2}%",
+            self.player_bust_rate() * 100.0
+        )?;
+        writeln!(
+            f,
+            "Dealer bust rate: {:.2}%",
+            self.dealer_bust_rate() * 100.0
+        )?;
+        writeln!(f, "Outcomes:")?;
+
+        for (name, outcome) in [
+            ("Win", Outcome::Win),
+            ("Blackjack", Outcome::Blackjack),
+            ("Push", Outcome::Push),
+            ("Lose", Outcome::Lose),
+            ("Surrender", Outcome::Surrender),
+        ] {
+            writeln!(
+                f,
+                "  {:<10}{:>7.2}%",
+                name,
+                self.outcome_rate(outcome) * 100.0
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// plays whole games of war without any output
+#[derive(Clone, Debug)]
+pub struct WarSimulation {
+    pub players: usize,
+    pub games: u64,
+    // games still going after this many rounds go to the most cards
+    pub max_rounds: u32,
+    pub threads: usize,
+    pub seed: u64,
+}
+
+impl WarSimulation {
+    pub fn new(players: usize, games: u64, seed: u64) -> WarSimulation {
+        WarSimulation {
+            players,
+            games,
+            max_rounds: 10_000,
+            threads: thread::available_parallelism().map_or(1, |x| x.get()),
+            seed,
+        }
+    }
+
+    pub fn run(&self) -> Result<WarReport, CardsError> {
+        let threads = self.threads.max(1) as u64;
+
+        let mut games = thread::scope(|scope| {
+            let workers = (0..threads)
+                .map(|first| {
+                    scope.spawn(move || {
+                        (first..self.games)
+                            .step_by(threads as usize)
+                            .map(|i| self.play(i))
+                            .collect::<Result<Vec<WarGame>, CardsError>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let mut games = Vec::new();
+
+            for worker in workers {
+                games.extend(worker.join().map_err(|_| CardsError::ThreadFailed)??);
+            }
+
+            Ok::<_, CardsError>(games)
+        })?;
+
+        games.sort_by_key(|x| x.game);
+
+        Ok(WarReport {
+            players: self.players,
+            games,
+        })
+    }
+
+    // every game has its own seed, so the results don't depend on the threads
+    fn play(&self, game: u64) -> Result<WarGame, CardsError> {
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(game));
+        let names = (1..=self.players)
+            .map(|x| format!("Player {}", x))
+            .collect();
+        let mut war = War::new(names, &mut rng, HeadlessUi)?;
+        war.max_rounds = Some(self.max_rounds);
+
+        let winners = loop {
+            if let RoundOutcome::GameOver { winners, .. } = war.round()? {
+                break winners;
+            }
+        };
+
+        Ok(WarGame {
+            game,
+            rounds: war.stats.rounds,
+            wars: war.stats.wars,
+            multi_way_wars: war.stats.multi_way_wars,
+            ending: war.stats.ending.unwrap_or(WarEnding::Finished),
+            winner: (winners.len() == 1).then(|| winners[0]),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WarGame {
+    pub game: u64,
+    pub rounds: u32,
+    pub wars: u32,
+    pub multi_way_wars: u32,
+    pub ending: WarEnding,
+    // none when a tie-break was tied too
+    pub winner: Option<i32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct WarReport {
+    pub players: usize,
+    pub games: Vec<WarGame>,
+}
+
+impl WarReport {
+    pub fn endings(&self, ending: WarEnding) -> usize {
+        self.games.iter().filter(|x| x.ending == ending).count()
+    }
+}
+
+// one row per game, the winner is empty when the tie-break was tied
+pub fn write_csv(reports: &[WarReport], out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "game,players,rounds,wars,multi_way_wars,ending,winner")?;
+
+    for report in reports {
+        for game in &report.games {
+            let winner = game.winner.map_or(String::new(), |x| (x + 1).to_string());
+
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                game.game,
+                report.players,
+                game.rounds,
+                game.wars,
+                game.multi_way_wars,
+                game.ending,
+                winner
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+// min, quartiles and max, with the mean
+fn write_distribution(f: &mut Formatter, name: &str, mut values: Vec<u32>) -> fmt::Result {
+    if values.is_empty() {
+        return Ok(());
+    }
+
+    values.sort_unstable();
+
+    let at = |x: f64| values[((values.len() - 1) as f64 * x).round() as usize];
+    let mean = values.iter().map(|x| f64::from(*x)).sum::<f64>() / values.len() as f64;
+
+    writeln!(
+        f,
+        "{:<16}mean {:.1}, min {}, 25% {}, median {}, 75% {}, max {}",
+        name,
+        mean,
+        at(0.0),
+        at(0.25),
+        at(0.5),
+        at(0.75),
+        at(1.0)
+    )
+}
+
+impl Display for WarReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(f, "Players: {}, games: {}", self.players, self.games.len())?;
+
+        let finished = self
+            .games
+            .iter()
+            .filter(|x| x.ending == WarEnding::Finished)
+            .map(|x| x.rounds)
+            .collect::<Vec<u32>>();
+        write_distribution(f, "Game length:", finished)?;
+        write_distribution(f, "Wars:", self.games.iter().map(|x| x.wars).collect())?;
+        write_distribution(
+            f,
+            "Multi-way wars:",
+            self.games.iter().map(|x| x.multi_way_wars).collect(),
+        )?;
+
+        let multi_way = self.games.iter().filter(|x| x.multi_way_wars > 0).count();
+        writeln!(
+            f,
+            "Games with a multi-way war: {} ({:.1}%)",
+            multi_way,
+            multi_way as f64 * 100.0 / self.games.len().max(1) as f64
+        )?;
+
+        for (name, ending) in [
+            ("Loops detected", WarEnding::Loop),
+            ("Stopped at the round limit", WarEnding::RoundLimit),
+        ] {
+            let count = self.endings(ending);
+            writeln!(
+                f,
+                "{}: {} ({:.1}%)",
+                name,
+                count,
+                count as f64 * 100.0 / self.games.len().max(1) as f64
+            )?;
+        }
+
+        for player in 0..self.players as i32 {
+            let wins = self
+                .games
+                .iter()
+                .filter(|x| x.winner == Some(player))
+                .count();
+            writeln!(f, "Player {} won {} games", player + 1, wins)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,299 @@
+use crate::blackjack::{hand_value, BlackJackRules};
+use crate::card::Card;
+use crate::controller::Action;
+
+// one cell of a strategy chart, with what to do when the first choice is
+// not allowed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Play {
+    Hit,
+    Stand,
+    // double, otherwise hit
+    DoubleHit,
+    // double, otherwise stand
+    DoubleStand,
+    Split,
+    // split when doubling after a split is allowed, otherwise hit
+    SplitIfDouble,
+    // surrender, otherwise hit
+    SurrenderHit,
+    // surrender, otherwise stand
+    SurrenderStand,
+    // surrender, otherwise split
+    SurrenderSplit,
+}
+
+// dealer up-cards 2 to 10, then the ace
+const UPCARDS: usize = 10;
+
+// basic strategy charts generated for one set of table rules
+#[derive(Clone, Debug)]
+pub struct Strategy {
+    // indexed by total, 4 to 21
+    hard: [[Play; UPCARDS]; 22],
+    // indexed by total, 13 to 21
+    soft: [[Play; UPCARDS]; 22],
+    // indexed by the value of one card of the pair, 2 to 11
+    pairs: [[Play; UPCARDS]; 12],
+    double_after_split: bool,
+}
+
+impl Strategy {
+    pub fn new(rules: &BlackJackRules) -> Strategy {
+        let h17 = rules.dealer_hits_soft_17;
+        let mut strategy = Strategy {
+            hard: [[Play::Hit; UPCARDS]; 22],
+            soft: [[Play::Hit; UPCARDS]; 22],
+            pairs: [[Play::Hit; UPCARDS]; 12],
+            double_after_split: rules.double_after_split,
+        };
+
+        for up in 2..=11 {
+            let i = up as usize - 2;
+
+            for total in 4..=21 {
+                strategy.hard[total as usize][i] = hard_play(total, up, h17);
+            }
+
+            for total in 13..=21 {
+                strategy.soft[total as usize][i] = soft_play(total, up, h17);
+            }
+
+            for card in 2..=11 {
+                strategy.pairs[card as usize][i] = pair_play(card, up, h17);
+            }
+        }
+
+        if !rules.surrender {
+            for row in strategy.hard.iter_mut().chain(strategy.pairs.iter_mut()) {
+                for play in row.iter_mut() {
+                    *play = match *play {
+                        Play::SurrenderHit => Play::Hit,
+                        Play::SurrenderStand => Play::Stand,
+                        Play::SurrenderSplit => Play::Split,
+                        play => play,
+                    };
+                }
+            }
+        }
+
+        strategy
+    }
+
+    // the chart's cell, before looking at what is allowed right now
+    pub fn play(&self, cards: &[Card], upcard: Card) -> Play {
+        let up = upcard.rank.blackjack_value() as usize - 2;
+
+        if cards.len() == 2 && cards[0].rank.blackjack_value() == cards[1].rank.blackjack_value() {
+            let play = self.pairs[cards[0].rank.blackjack_value() as usize][up];
+
+            // hit in the pairs chart means the pair is played by its total
+            if play != Play::Hit {
+                return play;
+            }
+        }
+
+        self.by_total(cards, up)
+    }
+
+    fn by_total(&self, cards: &[Card], up: usize) -> Play {
+        let value = hand_value(cards);
+
+        if value.total > 21 {
+            Play::Stand
+        } else if value.soft && value.total >= 13 {
+            self.soft[value.total as usize][up]
+        } else if value.soft {
+            // two aces that can't be split
+            Play::Hit
+        } else {
+            self.hard[value.total.max(4) as usize][up]
+        }
+    }
+
+    // the best of the actions that are allowed right now
+    pub fn recommend(&self, cards: &[Card], upcard: Card, actions: &[Action]) -> Action {
+        let allowed = |x: Action| actions.contains(&x);
+        let up = upcard.rank.blackjack_value() as usize - 2;
+
+        let play = match self.play(cards, upcard) {
+            Play::Split if allowed(Action::Split) => return Action::Split,
+            Play::SplitIfDouble if allowed(Action::Split) && self.double_after_split => {
+                return Action::Split
+            }
+            Play::SurrenderSplit if allowed(Action::Surrender) => return Action::Surrender,
+            Play::SurrenderSplit if allowed(Action::Split) => return Action::Split,
+            // a pair that is not split is played by its total
+            Play::Split | Play::SplitIfDouble | Play::SurrenderSplit => self.by_total(cards, up),
+            play => play,
+        };
+
+        let best = match play {
+            Play::DoubleHit | Play::DoubleStand if allowed(Action::Double) => Action::Double,
+            Play::SurrenderHit | Play::SurrenderStand if allowed(Action::Surrender) => {
+                Action::Surrender
+            }
+            Play::Stand | Play::DoubleStand | Play::SurrenderStand => Action::Stand,
+            _ => Action::Hit,
+        };
+
+        // split aces may only be allowed to stand
+        [best, Action::Stand, Action::Hit]
+            .into_iter()
+            .chain(actions.iter().copied())
+            .find(|x| allowed(*x))
+            .unwrap_or(best)
+    }
+}
+
+fn hard_play(total: i32, up: i32, h17: bool) -> Play {
+    match total {
+        ..=8 => Play::Hit,
+        9 if (3..=6).contains(&up) => Play::DoubleHit,
+        10 if up <= 9 => Play::DoubleHit,
+        11 if up <= 10 || h17 => Play::DoubleHit,
+        12 if (4..=6).contains(&up) => Play::Stand,
+        13..=16 if up <= 6 => Play::Stand,
+        15 if up == 10 || (up == 11 && h17) => Play::SurrenderHit,
+        16 if up >= 9 => Play::SurrenderHit,
+        17 if up == 11 && h17 => Play::SurrenderStand,
+        17.. => Play::Stand,
+        _ => Play::Hit,
+    }
+}
+
+fn soft_play(total: i32, up: i32, h17: bool) -> Play {
+    match total {
+        13 | 14 if (5..=6).contains(&up) => Play::DoubleHit,
+        15 | 16 if (4..=6).contains(&up) => Play::DoubleHit,
+        17 if (3..=6).contains(&up) => Play::DoubleHit,
+        18 if (3..=6).contains(&up) || (up == 2 && h17) => Play::DoubleStand,
+        18 if up <= 8 => Play::Stand,
+        19 if up == 6 && h17 => Play::DoubleStand,
+        19.. => Play::Stand,
+        _ => Play::Hit,
+    }
+}
+
+// hit means "don't split, play the total"
+fn pair_play(card: i32, up: i32, h17: bool) -> Play {
+    match card {
+        11 => Play::Split,
+        10 | 5 => Play::Hit,
+        9 if up == 7 || up >= 10 => Play::Hit,
+        9 => Play::Split,
+        8 if up == 11 && h17 => Play::SurrenderSplit,
+        8 => Play::Split,
+        7 if up <= 7 => Play::Split,
+        6 if up == 2 => Play::SplitIfDouble,
+        6 if up <= 6 => Play::Split,
+        4 if (5..=6).contains(&up) => Play::SplitIfDouble,
+        2 | 3 if up <= 3 => Play::SplitIfDouble,
+        2 | 3 if up <= 7 => Play::Split,
+        _ => Play::Hit,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::parse_hand;
+
+    const ALL: [Action; 5] = [
+        Action::Hit,
+        Action::Stand,
+        Action::Double,
+        Action::Split,
+        Action::Surrender,
+    ];
+
+    fn recommend(rules: &BlackJackRules, hand: &str, up: &str, actions: &[Action]) -> Action {
+        let cards = parse_hand(hand).unwrap();
+
+        Strategy::new(rules).recommend(&cards, up.parse().unwrap(), actions)
+    }
+
+    #[test]
+    fn surrenders_16_against_10_when_allowed() {
+        let rules = BlackJackRules::default();
+
+        assert_eq!(recommend(&rules, "10s 6h", "kd", &ALL), Action::Surrender);
+        assert_eq!(
+            recommend(&rules, "10s 6h", "kd", &[Action::Hit, Action::Stand]),
+            Action::Hit
+        );
+    }
+
+    #[test]
+    fn plays_pairs_that_cant_be_split() {
+        let rules = BlackJackRules {
+            dealer_hits_soft_17: true,
+            ..BlackJackRules::default()
+        };
+        let actions = [Action::Hit, Action::Stand, Action::Surrender];
+
+        assert_eq!(recommend(&rules, "8s 8h", "ad", &ALL), Action::Surrender);
+        assert_eq!(
+            recommend(&rules, "8s 8h", "ad", &actions),
+            Action::Surrender
+        );
+        assert_eq!(
+            recommend(&rules, "8s 8h", "6d", &actions[..2]),
+            Action::Stand
+        );
+        assert_eq!(recommend(&rules, "as ah", "6d", &ALL), Action::Split);
+        assert_eq!(recommend(&rules, "as ah", "6d", &actions[..2]), Action::Hit);
+        assert_eq!(
+            recommend(&rules, "as ah", "6d", &[Action::Stand]),
+            Action::Stand
+        );
+        assert_eq!(
+            recommend(&rules, "10s 6h", "kd", &[Action::Stand]),
+            Action::Stand
+        );
+    }
+
+    #[test]
+    fn doubles_11_against_an_ace_only_on_h17() {
+        let s17 = BlackJackRules::default();
+        let h17 = BlackJackRules {
+            dealer_hits_soft_17: true,
+            ..BlackJackRules::default()
+        };
+
+        assert_eq!(recommend(&s17, "6s 5h", "ad", &ALL), Action::Hit);
+        assert_eq!(recommend(&h17, "6s 5h", "ad", &ALL), Action::Double);
+    }
+
+    #[test]
+    fn plays_soft_18() {
+        let rules = BlackJackRules::default();
+
+        assert_eq!(recommend(&rules, "as 7h", "9d", &ALL), Action::Hit);
+        assert_eq!(recommend(&rules, "as 7h", "3d", &ALL), Action::Double);
+        assert_eq!(recommend(&rules, "as 7h", "3d", &ALL[..2]), Action::Stand);
+        assert_eq!(recommend(&rules, "as 7h", "7d", &ALL), Action::Stand);
+    }
+
+    #[test]
+    fn plays_hard_12() {
+        let rules = BlackJackRules::default();
+
+        assert_eq!(recommend(&rules, "10s 2h", "4d", &ALL), Action::Stand);
+        assert_eq!(recommend(&rules, "10s 2h", "2d", &ALL), Action::Hit);
+    }
+
+    #[test]
+    fn drops_surrender_from_the_charts() {
+        let rules = BlackJackRules {
+            surrender: false,
+            ..BlackJackRules::default()
+        };
+        let cards = parse_hand("10s 6h").unwrap();
+
+        assert_eq!(
+            Strategy::new(&rules).play(&cards, "kd".parse().unwrap()),
+            Play::Hit
+        );
+    }
+}
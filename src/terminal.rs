@@ -0,0 +1,90 @@
+use std::io::{stdin, stdout, Write};
+
+use crate::card::Card;
+use crate::error::CardsError;
+use crate::render::{Renderer, Style};
+use crate::ui::{Option, UserInterface};
+
+pub fn read_line() -> Result<String, CardsError> {
+    let mut value = String::new();
+    stdout().flush().map_err(|_| CardsError::Quit)?;
+
+    // nothing read means ctrl-d or a closed pipe
+    match stdin().read_line(&mut value) {
+        Ok(0) | Err(_) => Err(CardsError::Quit),
+        Ok(_) => Ok(value),
+    }
+}
+
+#[macro_export]
+macro_rules! console_clear {
+    () => {
+        print!("\x1B[2J\x1B[1;1H");
+    };
+}
+
+pub use crate::console_clear;
+
+fn header_start() {
+    println!("{}\n", "---".repeat(20));
+}
+
+fn header_end() {
+    println!("\n{}", "---".repeat(20));
+}
+
+#[derive(Default)]
+pub struct TerminalUi {
+    pub renderer: Renderer,
+}
+
+impl TerminalUi {
+    pub fn new(renderer: Renderer) -> TerminalUi {
+        TerminalUi { renderer }
+    }
+}
+
+impl UserInterface for TerminalUi {
+    fn clear(&mut self) {
+        console_clear!();
+        header_start();
+    }
+
+    fn pause(&mut self) -> Result<(), CardsError> {
+        header_end();
+        self.read_command("Press enter to continue...", "Press enter to continue.")?;
+
+        Ok(())
+    }
+
+    fn show_message(&mut self, message: &str) {
+        println!("{}", message);
+    }
+
+    fn show_hand(&mut self, label: &str, cards: &[Card]) {
+        if self.renderer.style == Style::Art {
+            println!("{}:\n{}", label, self.renderer.hand(cards));
+        } else {
+            println!("{}: {}", label, self.renderer.hand(cards));
+        }
+    }
+
+    fn show_options(&mut self, ask: &str, options: &[Option]) {
+        header_start();
+        println!("{}", ask);
+        for option in options {
+            println!("{}) {}", option.value, option.name);
+        }
+        println!("\nh) Help\nq) Quit");
+        header_end();
+    }
+
+    fn read_line(&mut self, ask: &str) -> Result<String, CardsError> {
+        print!("{}", ask);
+        read_line()
+    }
+
+    fn card_name(&self, card: &Card) -> String {
+        self.renderer.card(card)
+    }
+}
@@ -0,0 +1,359 @@
+use std::io::{stdout, Stdout};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style as TextStyle};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Table as TableWidget,
+};
+use ratatui::{Frame, Terminal};
+
+use crate::card::Card;
+use crate::error::CardsError;
+use crate::render::{Renderer, Style};
+use crate::ui::{Option, Table, UserInterface};
+
+const HELP: &str = "Use the arrow keys and enter, or type a number. Press q to quit.";
+
+fn terminal_error(error: std::io::Error) -> CardsError {
+    CardsError::Terminal(error.to_string())
+}
+
+// full-screen interface: the table stays on top, messages scroll in a log
+// below it and menus are picked with the arrow keys
+pub struct TuiUi {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+    renderer: Renderer,
+    table: Table,
+    log: Vec<String>,
+    prompt: String,
+    input: String,
+    options: Vec<(i32, String)>,
+    selected: usize,
+}
+
+impl TuiUi {
+    pub fn new() -> Result<TuiUi, CardsError> {
+        TuiUi::with_style(Style::Symbol)
+    }
+
+    // the table is drawn one card per cell, so art cards show as symbols
+    pub fn with_style(style: Style) -> Result<TuiUi, CardsError> {
+        enable_raw_mode().map_err(terminal_error)?;
+        execute!(stdout(), EnterAlternateScreen).map_err(terminal_error)?;
+
+        let mut terminal =
+            Terminal::new(CrosstermBackend::new(stdout())).map_err(terminal_error)?;
+        terminal.clear().map_err(terminal_error)?;
+
+        Ok(TuiUi {
+            terminal,
+            renderer: Renderer::new(style, false),
+            table: Table::default(),
+            log: Vec::new(),
+            prompt: String::new(),
+            input: String::new(),
+            options: Vec::new(),
+            selected: 0,
+        })
+    }
+
+    fn card_span(renderer: &Renderer, card: &Card) -> Span<'static> {
+        let text = renderer.card(card);
+
+        if card.visible && card.suit.is_red() {
+            Span::styled(text, TextStyle::default().fg(Color::Red))
+        } else {
+            Span::raw(text)
+        }
+    }
+
+    fn cards_line(renderer: &Renderer, cards: &[Card]) -> Line<'static> {
+        let mut spans = Vec::new();
+
+        for card in cards {
+            spans.push(TuiUi::card_span(renderer, card));
+            spans.push(Span::raw(" "));
+        }
+
+        Line::from(spans)
+    }
+
+    fn draw(&mut self) -> Result<(), CardsError> {
+        let TuiUi {
+            terminal,
+            renderer,
+            table,
+            log,
+            prompt,
+            input,
+            options,
+            selected,
+        } = self;
+
+        terminal
+            .draw(|frame| {
+                TuiUi::render(
+                    frame, renderer, table, log, prompt, input, options, *selected,
+                )
+            })
+            .map_err(terminal_error)?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render(
+        frame: &mut Frame,
+        renderer: &Renderer,
+        table: &Table,
+        log: &[String],
+        prompt: &str,
+        input: &str,
+        options: &[(i32, String)],
+        selected: usize,
+    ) {
+        let floor_height = if table.floor.is_empty() { 0 } else { 3 };
+        let prompt_height = if options.is_empty() {
+            3
+        } else {
+            options.len() as u16 + 3
+        };
+
+        let [table_area, floor_area, log_area, prompt_area] = *Layout::vertical([
+            Constraint::Length(table.seats.len() as u16 + 3),
+            Constraint::Length(floor_height),
+            Constraint::Min(3),
+            Constraint::Length(prompt_height),
+        ])
+        .split(frame.size()) else {
+            return;
+        };
+
+        let rows = table.seats.iter().map(|seat| {
+            Row::new(vec![
+                Cell::from(seat.name.clone()),
+                Cell::from(TuiUi::cards_line(renderer, &seat.cards)),
+                Cell::from(seat.detail.clone()),
+            ])
+        });
+
+        let widths = [
+            Constraint::Length(16),
+            Constraint::Min(20),
+            Constraint::Length(20),
+        ];
+
+        frame.render_widget(
+            TableWidget::new(rows, widths)
+                .header(
+                    Row::new(vec!["Player", "Cards", ""])
+                        .style(TextStyle::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(Block::default().borders(Borders::ALL).title("Table")),
+            table_area,
+        );
+
+        if !table.floor.is_empty() {
+            frame.render_widget(
+                Paragraph::new(TuiUi::cards_line(renderer, &table.floor))
+                    .block(Block::default().borders(Borders::ALL).title("Floor")),
+                floor_area,
+            );
+        }
+
+        TuiUi::render_log(frame, log, log_area);
+
+        if options.is_empty() {
+            frame.render_widget(
+                Paragraph::new(format!("{}{}_", prompt, input))
+                    .block(Block::default().borders(Borders::ALL)),
+                prompt_area,
+            );
+        } else {
+            let items = options
+                .iter()
+                .map(|(value, name)| ListItem::new(format!("{}) {}", value, name)))
+                .collect::<Vec<ListItem>>();
+
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(prompt.to_string()),
+                )
+                .highlight_style(TextStyle::default().add_modifier(Modifier::REVERSED))
+                .highlight_symbol("> ");
+
+            let mut state = ListState::default().with_selected(Some(selected));
+            frame.render_stateful_widget(list, prompt_area, &mut state);
+        }
+    }
+
+    // only the newest lines that fit are shown
+    fn render_log(frame: &mut Frame, log: &[String], area: Rect) {
+        let height = area.height.saturating_sub(2) as usize;
+        let lines = log
+            .iter()
+            .skip(log.len().saturating_sub(height))
+            .map(|x| Line::from(x.clone()))
+            .collect::<Vec<Line>>();
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Log")),
+            area,
+        );
+    }
+
+    fn next_key(&mut self) -> Result<KeyEvent, CardsError> {
+        loop {
+            self.draw()?;
+
+            if let Event::Key(key) = event::read().map_err(terminal_error)? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+
+                // raw mode swallows ctrl-c, so it is handled here
+                if key.modifiers.contains(KeyModifiers::CONTROL)
+                    && matches!(key.code, KeyCode::Char('c') | KeyCode::Char('d'))
+                {
+                    return Err(CardsError::Quit);
+                }
+
+                return Ok(key);
+            }
+        }
+    }
+}
+
+impl UserInterface for TuiUi {
+    fn clear(&mut self) {
+        if !self.log.is_empty() {
+            self.log.push(String::new());
+        }
+    }
+
+    fn pause(&mut self) -> Result<(), CardsError> {
+        self.prompt = "Press enter to continue...".to_string();
+
+        let result = loop {
+            match self.next_key()?.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Char('q') | KeyCode::Esc => break Err(CardsError::Quit),
+                _ => continue,
+            }
+        };
+
+        self.prompt.clear();
+        result
+    }
+
+    fn show_message(&mut self, message: &str) {
+        self.log.extend(message.lines().map(str::to_string));
+    }
+
+    fn show_hand(&mut self, label: &str, cards: &[Card]) {
+        self.log
+            .push(format!("{}: {}", label, self.renderer.hand(cards)));
+    }
+
+    fn show_options(&mut self, ask: &str, options: &[Option]) {
+        self.log.push(ask.to_string());
+
+        for option in options {
+            self.log.push(format!("{}) {}", option.value, option.name));
+        }
+    }
+
+    fn read_line(&mut self, ask: &str) -> Result<String, CardsError> {
+        self.prompt = ask.to_string();
+        self.input.clear();
+
+        let result = loop {
+            match self.next_key()?.code {
+                KeyCode::Enter => break Ok(self.input.clone()),
+                KeyCode::Esc => break Err(CardsError::Quit),
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                _ => continue,
+            }
+        };
+
+        if let Ok(input) = &result {
+            self.log.push(format!("{}{}", self.prompt, input));
+        }
+
+        self.prompt.clear();
+        self.input.clear();
+        result
+    }
+
+    fn card_name(&self, card: &Card) -> String {
+        self.renderer.card(card)
+    }
+
+    fn show_table(&mut self, table: &Table) {
+        self.table = table.clone();
+    }
+
+    fn choose_option(&mut self, ask: &str, options: &[Option]) -> Result<i32, CardsError> {
+        if options.is_empty() {
+            return Err(CardsError::InvalidArgument(format!(
+                "nothing to choose from for \"{}\"",
+                ask
+            )));
+        }
+
+        self.prompt = ask.to_string();
+        self.options = options
+            .iter()
+            .map(|x| (x.value, x.name.to_string()))
+            .collect();
+        self.selected = 0;
+
+        let result = loop {
+            match self.next_key()?.code {
+                KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Down => self.selected = (self.selected + 1).min(options.len() - 1),
+                KeyCode::Enter => break Ok(options[self.selected].value),
+                KeyCode::Char('q') | KeyCode::Esc => break Err(CardsError::Quit),
+                KeyCode::Char('h') => self.log.push(HELP.to_string()),
+                KeyCode::Char(c) => {
+                    let value = c.to_digit(10).map(|x| x as i32);
+
+                    if let Some(option) = options.iter().find(|x| Some(x.value) == value) {
+                        break Ok(option.value);
+                    }
+                }
+                _ => continue,
+            }
+        };
+
+        if let Ok(value) = result {
+            if let Some(option) = options.iter().find(|x| x.value == value) {
+                self.log.push(format!("{} {}", ask, option.name));
+            }
+        }
+
+        self.prompt.clear();
+        self.options.clear();
+        result
+    }
+}
+
+impl Drop for TuiUi {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(stdout(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
@@ -1,89 +1,285 @@
-use std::io::{stdin, stdout, Write};
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+
+use crate::card::Card;
+use crate::error::CardsError;
 
 pub struct Option {
     pub name: &'static str,
     pub value: i32,
 }
 
-pub(super) fn promptfn() -> String {
-    let mut value = String::new();
-    stdout().flush().unwrap();
-    stdin().read_line(&mut value).unwrap();
-
-    value
+#[derive(Clone, Debug, Default)]
+pub struct Seat {
+    pub name: String,
+    pub cards: Vec<Card>,
+    // stack size, hand total, ...
+    pub detail: String,
 }
 
-macro_rules! console_clear {
-    () => {
-        print!("\x1B[2J\x1B[1;1H");
-    };
+// what is on the table right now, for interfaces that keep it on screen
+#[derive(Clone, Debug, Default)]
+pub struct Table {
+    pub seats: Vec<Seat>,
+    pub floor: Vec<Card>,
 }
 
-macro_rules! header_start {
-    () => {
-        println!("{}\n", "---".to_string().repeat(20));
-    };
-
-    ($clear:expr) => {
-        if !$clear {
-            console_clear!();
-            header_start!();
-        } else {
-            header_start!();
+// every prompt below understands "q" to quit and "h" for help, and quits at
+// the end of input
+pub trait UserInterface {
+    // starts a new screen
+    fn clear(&mut self);
+    // waits for the player to read the screen
+    fn pause(&mut self) -> Result<(), CardsError>;
+    fn show_message(&mut self, message: &str);
+    fn show_hand(&mut self, label: &str, cards: &[Card]);
+    fn show_options(&mut self, ask: &str, options: &[Option]);
+    // a single line of raw input, or CardsError::Quit once there is no more
+    fn read_line(&mut self, ask: &str) -> Result<String, CardsError>;
+
+    fn card_name(&self, card: &Card) -> String {
+        card.to_string()
+    }
+
+    fn show_table(&mut self, _table: &Table) {}
+
+    // reads a line, handling quit and help; None means help was shown
+    fn read_command(
+        &mut self,
+        ask: &str,
+        help: &str,
+    ) -> Result<std::option::Option<String>, CardsError> {
+        let input = self.read_line(ask)?;
+
+        match input.trim().to_lowercase().as_str() {
+            "q" | "quit" => Err(CardsError::Quit),
+            "h" | "help" => {
+                self.show_message(&format!("{} Type q to quit.", help));
+                Ok(None)
+            }
+            _ => Ok(Some(input.trim().to_string())),
         }
-    };
-}
+    }
+
+    fn choose_option(&mut self, ask: &str, options: &[Option]) -> Result<i32, CardsError> {
+        self.show_options(ask, options);
+
+        let help = "Type the number of one of the options above.";
+
+        loop {
+            let input = match self.read_command("", help)? {
+                Some(x) => x,
+                None => continue,
+            };
+
+            match input.parse::<i32>() {
+                Ok(res) if options.iter().any(|x| x.value == res) => return Ok(res),
+                Ok(res) => self.show_message(&CardsError::InvalidOption(res).to_string()),
+                Err(_) => self.show_message(&CardsError::InvalidInput(input).to_string()),
+            }
+        }
+    }
 
-macro_rules! header_end {
-    () => {
-        println!("\n{}", "---".to_string().repeat(20));
-    };
+    fn ask_number(
+        &mut self,
+        ask: &str,
+        range: RangeInclusive<i32>,
+        default: std::option::Option<i32>,
+    ) -> Result<i32, CardsError> {
+        let mut help = format!("Type a number from {} to {}.", range.start(), range.end());
+
+        if let Some(x) = default {
+            help += &format!(" Leave it empty for {}.", x);
+        }
+
+        loop {
+            let input = match self.read_command(ask, &help)? {
+                Some(x) => x,
+                None => continue,
+            };
+
+            match (input.parse::<i32>(), default) {
+                (Ok(res), _) if range.contains(&res) => return Ok(res),
+                (Err(_), Some(x)) if input.is_empty() => return Ok(x),
+                _ => self.show_message(&format!("{} {}", CardsError::InvalidInput(input), help)),
+            }
+        }
+    }
+
+    fn ask_yes_no(
+        &mut self,
+        ask: &str,
+        default: std::option::Option<bool>,
+    ) -> Result<bool, CardsError> {
+        let ask = match default {
+            Some(true) => format!("{} [Y/n] ", ask),
+            Some(false) => format!("{} [y/N] ", ask),
+            None => format!("{} [y/n] ", ask),
+        };
+        let help = "Type y for yes or n for no.";
+
+        loop {
+            let input = match self.read_command(&ask, help)? {
+                Some(x) => x,
+                None => continue,
+            };
+
+            match (input.to_lowercase().as_str(), default) {
+                ("y" | "yes", _) => return Ok(true),
+                ("n" | "no", _) => return Ok(false),
+                ("", Some(x)) => return Ok(x),
+                _ => self.show_message(&format!("{} {}", CardsError::InvalidInput(input), help)),
+            }
+        }
+    }
+
+    fn ask_text(&mut self, ask: &str) -> Result<String, CardsError> {
+        loop {
+            match self.read_command(ask, "Type some text and press enter.")? {
+                Some(x) if !x.is_empty() => return Ok(x),
+                _ => continue,
+            }
+        }
+    }
 }
 
-macro_rules! prompt {
-	($($args:tt)*) => {{
-		let formatted = std::fmt::format(format_args!($($args)*));
+impl<U: UserInterface + ?Sized> UserInterface for &mut U {
+    fn clear(&mut self) {
+        (**self).clear()
+    }
+
+    fn pause(&mut self) -> Result<(), CardsError> {
+        (**self).pause()
+    }
 
-		print!("{}", formatted);
-		let value = crate::ui::promptfn();
+    fn show_message(&mut self, message: &str) {
+        (**self).show_message(message)
+    }
 
-		value
-	}};
+    fn show_hand(&mut self, label: &str, cards: &[Card]) {
+        (**self).show_hand(label, cards)
+    }
+
+    fn show_options(&mut self, ask: &str, options: &[Option]) {
+        (**self).show_options(ask, options)
+    }
+
+    fn read_line(&mut self, ask: &str) -> Result<String, CardsError> {
+        (**self).read_line(ask)
+    }
+
+    fn card_name(&self, card: &Card) -> String {
+        (**self).card_name(card)
+    }
+
+    fn show_table(&mut self, table: &Table) {
+        (**self).show_table(table)
+    }
+
+    fn choose_option(&mut self, ask: &str, options: &[Option]) -> Result<i32, CardsError> {
+        (**self).choose_option(ask, options)
+    }
+
+    fn ask_number(
+        &mut self,
+        ask: &str,
+        range: RangeInclusive<i32>,
+        default: std::option::Option<i32>,
+    ) -> Result<i32, CardsError> {
+        (**self).ask_number(ask, range, default)
+    }
+
+    fn ask_yes_no(
+        &mut self,
+        ask: &str,
+        default: std::option::Option<bool>,
+    ) -> Result<bool, CardsError> {
+        (**self).ask_yes_no(ask, default)
+    }
+
+    fn ask_text(&mut self, ask: &str) -> Result<String, CardsError> {
+        (**self).ask_text(ask)
+    }
+}
+
+// answers prompts from a list and records everything that is shown
+#[derive(Default)]
+pub struct ScriptedUi {
+    pub answers: VecDeque<String>,
+    pub output: Vec<String>,
 }
 
-macro_rules! prompt_options {
-    ($ask:expr, $options:expr) => {{
-        header_start!();
-        println!("{}", $ask);
-        for option in $options {
-            println!("{}) {}", option.value, option.name);
+impl ScriptedUi {
+    pub fn new(answers: &[&str]) -> ScriptedUi {
+        ScriptedUi {
+            answers: answers.iter().map(|x| x.to_string()).collect(),
+            output: Vec::new(),
         }
-        header_end!();
+    }
+}
 
-        let res: i32 = prompt!("").trim().parse().unwrap();
-        assert!($options.iter().any(|x| x.value == res), "E_INVALID_OPTION");
+impl UserInterface for ScriptedUi {
+    fn clear(&mut self) {}
 
-        res
-    }};
-}
+    fn pause(&mut self) -> Result<(), CardsError> {
+        Ok(())
+    }
 
-macro_rules! prompt_headers {
-	($($args:tt)*) => {{
-		let formatted = std::fmt::format(format_args!($($args)*));
+    fn show_message(&mut self, message: &str) {
+        self.output.push(message.to_string());
+    }
 
-		header_start!();
-		println!("{}", formatted);
-		header_end!();
+    fn show_hand(&mut self, label: &str, cards: &[Card]) {
+        let cards = cards
+            .iter()
+            .map(|x| {
+                if x.visible {
+                    x.to_string()
+                } else {
+                    "?".to_string()
+                }
+            })
+            .collect::<Vec<String>>();
 
-		let res = prompt!("");
+        self.output.push(format!("{}: {}", label, cards.join(", ")));
+    }
 
-		res
-	}};
+    fn show_options(&mut self, ask: &str, options: &[Option]) {
+        self.output.push(ask.to_string());
+
+        for option in options {
+            self.output
+                .push(format!("{}) {}", option.value, option.name));
+        }
+    }
+
+    fn read_line(&mut self, ask: &str) -> Result<String, CardsError> {
+        if !ask.is_empty() {
+            self.output.push(ask.to_string());
+        }
+
+        self.answers.pop_front().ok_or(CardsError::Quit)
+    }
 }
 
-pub(crate) use console_clear;
-pub(crate) use header_end;
-pub(crate) use header_start;
-pub(crate) use prompt;
-pub(crate) use prompt_headers;
-pub(crate) use prompt_options;
+// shows nothing and has no input, for computer players only
+#[derive(Default)]
+pub struct HeadlessUi;
+
+impl UserInterface for HeadlessUi {
+    fn clear(&mut self) {}
+
+    fn pause(&mut self) -> Result<(), CardsError> {
+        Ok(())
+    }
+
+    fn show_message(&mut self, _message: &str) {}
+
+    fn show_hand(&mut self, _label: &str, _cards: &[Card]) {}
+
+    fn show_options(&mut self, _ask: &str, _options: &[Option]) {}
+
+    fn read_line(&mut self, _ask: &str) -> Result<String, CardsError> {
+        Err(CardsError::Quit)
+    }
+}
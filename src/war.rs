@@ -1,36 +1,107 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+
+use rand::Rng;
+
 use crate::card::Card;
-use crate::game_common::Game;
+use crate::controller::ControllerKind;
+use crate::deck::Deck;
+use crate::error::CardsError;
+use crate::game_common::{check_player_count, leaders, Game, RoundOutcome, Standing};
 use crate::player::{Player, PlayerCard};
-use crate::ui::{header_end, header_start, prompt};
+use crate::ui::{Seat, Table, UserInterface};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WarEnding {
+    // one player has all the cards
+    Finished,
+    // the decks were the same as after an earlier round, so the game would
+    // repeat forever
+    Loop,
+    RoundLimit,
+}
+
+impl Display for WarEnding {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            WarEnding::Finished => write!(f, "finished"),
+            WarEnding::Loop => write!(f, "loop"),
+            WarEnding::RoundLimit => write!(f, "round_limit"),
+        }
+    }
+}
+
+// counted over the whole game
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WarStats {
+    pub rounds: u32,
+    // a war that is tied again counts twice
+    pub wars: u32,
+    // wars between three or more players
+    pub multi_way_wars: u32,
+    pub ending: Option<WarEnding>,
+}
 
-pub struct War {
+pub struct War<U: UserInterface> {
     pub players: Vec<Player>,
+    pub eliminated: Vec<Player>,
+    pub stats: WarStats,
+    // the player with the most cards wins once this many rounds are played
+    pub max_rounds: Option<u32>,
+    // hashes of everyone's deck after every round
+    seen: HashSet<u64>,
+    pub ui: U,
 }
 
-impl War {
-    pub fn new(player_names: Vec<String>) -> War {
-        assert!(player_names.len() > 1, "E_NOT_ENOUGH_PLAYERS");
-        assert!(player_names.len() < 5, "E_TOO_MANY_PLAYERS");
+impl<U: UserInterface> War<U> {
+    pub fn new(player_names: Vec<String>, rng: &mut impl Rng, ui: U) -> Result<War<U>, CardsError> {
+        let players = player_names
+            .into_iter()
+            .map(|x| (x, ControllerKind::Human))
+            .collect();
+
+        War::with_controllers(players, rng, ui)
+    }
+
+    // nobody makes choices in war, the kind only tells humans and computers apart
+    pub fn with_controllers(
+        seats: Vec<(String, ControllerKind)>,
+        rng: &mut impl Rng,
+        ui: U,
+    ) -> Result<War<U>, CardsError> {
+        check_player_count(seats.len(), 2, 4)?;
 
         let mut players = Vec::new();
-        let mut deck = Card::new_deck();
+        let mut deck = Deck::new(rng);
+        deck.shuffle();
 
-        let cards_per_player = (52.0 / player_names.len() as f64).floor() as i32;
-        let extra = 52 % player_names.len();
+        let cards_per_player = (52.0 / seats.len() as f64).floor() as i32;
+        let extra = 52 % seats.len();
 
-        for (i, name) in player_names.iter().enumerate() {
-            players.push(Player::new(
-                name.to_string(),
-                i.try_into().unwrap(),
+        for (i, (name, controller)) in seats.into_iter().enumerate() {
+            let mut player = Player::new(
+                name,
+                i as i32,
                 &mut deck,
                 cards_per_player + i32::from(i < extra),
-            ));
+            )?;
+            player.controller = controller;
+            players.push(player);
         }
 
-        War { players }
+        Ok(War {
+            players,
+            eliminated: Vec::new(),
+            stats: WarStats::default(),
+            max_rounds: None,
+            seen: HashSet::new(),
+            ui,
+        })
     }
 
-    pub fn draw(&mut self) -> Vec<PlayerCard> {
+    pub fn draw(&mut self) -> Result<Vec<PlayerCard>, CardsError> {
         let players = &mut self.players;
 
         let mut out = Vec::new();
@@ -45,158 +116,379 @@ impl War {
                 }
             };
 
-            println!("{} drew a {}", player.name, draw.card);
+            let message = format!("{} drew {}", player.name, self.ui.card_name(&draw.card));
+            self.ui.show_message(&message);
             cards.push(draw);
         }
 
-        self.remove_all(out);
+        self.remove_all(out)?;
 
-        cards
+        Ok(cards)
     }
 
-    pub fn win_game(&self) {
-        let winner = &self.players[0];
+    pub fn remove(&mut self, player_id: i32) -> Result<(), CardsError> {
+        let index = Player::player_index(&self.players, player_id)
+            .ok_or(CardsError::UnknownPlayer(player_id))?;
+        let player = self.players.remove(index);
 
-        println!("{} wins the game!", winner.name);
-        header_end!();
-        prompt!("Press enter to exit");
-        std::process::exit(0);
-    }
-
-    pub fn remove(&mut self, player_id: i32) {
-        let mut player: Option<Player> = None;
-
-        for i in 0..self.players.len() {
-            if self.players[i].id == player_id {
-                player = Some(self.players.remove(i));
-                break;
-            }
-        }
-
-        println!(
+        self.ui.show_message(&format!(
             "{} has no more cards and is out of the game!",
-            player.unwrap().name
-        );
+            player.name
+        ));
+        self.eliminated.push(player);
 
-        if self.players.len() == 1 {
-            self.win_game();
-        }
+        Ok(())
     }
 
-    pub fn remove_all(&mut self, player_ids: Vec<i32>) {
+    pub fn remove_all(&mut self, player_ids: Vec<i32>) -> Result<(), CardsError> {
         for player_id in player_ids {
-            self.remove(player_id);
+            self.remove(player_id)?;
         }
+
+        Ok(())
     }
 
-    pub fn war(&mut self, winners: &mut Vec<i32>, floor: &mut Vec<PlayerCard>) {
-        println!("\nWar!");
+    pub fn war(
+        &mut self,
+        winners: &mut Vec<i32>,
+        floor: &mut Vec<PlayerCard>,
+    ) -> Result<(), CardsError> {
+        self.ui.show_message("\nWar!");
+        self.stats.wars += 1;
+
+        if winners.len() > 2 {
+            self.stats.multi_way_wars += 1;
+        }
 
         let mut player_draws = Vec::new();
 
         for player in self.players.iter_mut() {
             if winners.contains(&player.id) {
                 let mut draws = player.fdraw_all(4);
-                let mut cmp: Option<PlayerCard> = None;
-
-                if draws.is_empty() {
-                    for i in (0..floor.len()).rev() {
-                        if floor[i].player_id == player.id {
-                            cmp = Some(floor.remove(i));
-                            break;
-                        }
-
-                        if i == 0 {
-                            panic!("E_UNKNOWN");
-                        }
-                    }
-                } else {
-                    cmp = Some(draws.pop().expect("E_UNKNOWN"));
-                }
 
-                let cmp_known = cmp.expect("E_UNKNOWN");
+                // a player without cards left plays their last card on the floor again
+                let cmp_known = match draws.pop() {
+                    Some(x) => x,
+                    None => {
+                        let index = floor
+                            .iter()
+                            .rposition(|x| x.player_id == player.id)
+                            .ok_or(CardsError::DeckExhausted)?;
 
-                println!(
-                    "{} drew {} unknown cards and a {}",
+                        floor.remove(index)
+                    }
+                };
+
+                let message = format!(
+                    "{} drew {} unknown cards and {}",
                     player.name,
                     draws.len(),
-                    cmp_known.card
+                    self.ui.card_name(&cmp_known.card)
                 );
+                self.ui.show_message(&message);
 
                 player_draws.push(cmp_known);
                 floor.extend(draws);
             }
         }
 
-        player_draws.sort_by(|a, b| b.card.value_id.cmp(&a.card.value_id));
+        player_draws.sort_by_key(|x| std::cmp::Reverse(x.card.rank));
 
-        let highest = player_draws[0].card.value_id;
+        let highest = player_draws[0].card.rank;
         winners.clear();
 
         for player_draw in player_draws.iter() {
-            if player_draw.card.value_id == highest {
+            if player_draw.card.rank == highest {
                 winners.push(player_draw.player_id);
             }
         }
 
-        floor.extend(player_draws.drain(..).collect::<Vec<PlayerCard>>());
+        floor.append(&mut player_draws);
+
+        Ok(())
+    }
+
+    pub fn table(&self, floor: &[PlayerCard]) -> Table {
+        Table {
+            seats: self
+                .players
+                .iter()
+                .map(|x| Seat {
+                    name: x.name.clone(),
+                    cards: Vec::new(),
+                    detail: format!("{} cards", x.deck.len()),
+                })
+                .collect(),
+            floor: floor.iter().map(|x| x.card).collect(),
+        }
+    }
+
+    // players without cards replay their last card, so a war between them
+    // would be tied forever
+    fn out_of_cards(&self, player_ids: &[i32]) -> bool {
+        self.players
+            .iter()
+            .filter(|x| player_ids.contains(&x.id))
+            .all(|x| x.deck.is_empty())
+    }
+
+    fn share_floor(
+        &mut self,
+        player_ids: &[i32],
+        floor: &mut Vec<PlayerCard>,
+    ) -> Result<(), CardsError> {
+        self.ui.show_message(
+            "\nNobody in the war has cards left to break the tie, so they share the floor.",
+        );
+
+        for (i, draw) in floor.drain(..).enumerate() {
+            let id = player_ids[i % player_ids.len()];
+            let player = self
+                .players
+                .iter_mut()
+                .find(|x| x.id == id)
+                .ok_or(CardsError::UnknownPlayer(id))?;
+
+            player.deck.push(draw.card);
+        }
+
+        Ok(())
+    }
+
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for player in &self.players {
+            player.id.hash(&mut hasher);
+            player.deck.hash(&mut hasher);
+        }
+
+        hasher.finish()
     }
 
-    fn round_win(&mut self, winner: i32, floor: &mut Vec<PlayerCard>) {
+    // the game can't be played out, so whoever has the most cards wins
+    fn tie_break(&mut self, ending: WarEnding) -> Result<RoundOutcome, CardsError> {
+        let reason = match ending {
+            WarEnding::Loop => format!(
+                "After {} rounds the cards are back in an order that was already played, so this game would never end.",
+                self.stats.rounds
+            ),
+            _ => format!("The game has reached the limit of {} rounds.", self.stats.rounds),
+        };
+
+        let standings = self.standings();
+        let winners = leaders(&standings);
+        let names = standings
+            .iter()
+            .filter(|x| winners.contains(&x.player_id))
+            .map(|x| x.name.as_str())
+            .collect::<Vec<&str>>();
+
+        self.ui.show_message(&format!("\n{}", reason));
+        self.ui.show_message(&format!(
+            "The most cards win: {} with {} cards.",
+            names.join(" and "),
+            standings[0].score
+        ));
+        self.ui.pause()?;
+
+        self.stats.ending = Some(ending);
+
+        Ok(RoundOutcome::GameOver { winners, standings })
+    }
+
+    fn round_win(&mut self, winner: i32, floor: &mut Vec<PlayerCard>) -> Result<(), CardsError> {
         let players = &mut self.players;
         let winner = players
             .iter_mut()
             .find(|e| e.id == winner)
-            .expect("E_UNKNOWN");
+            .ok_or(CardsError::UnknownPlayer(winner))?;
 
-        println!(
+        self.ui.show_message(&format!(
             "\n{} won the round and gains {} cards!",
             winner.name,
             floor.len()
-        );
+        ));
 
-        floor.iter().for_each(|e| println!("{}", e.card));
+        let cards = floor.iter().map(|x| x.card).collect::<Vec<Card>>();
+        self.ui.show_hand(&format!("{} gains", winner.name), &cards);
 
         winner
             .deck
             .extend(floor.drain(..).map(|x| x.card).collect::<Vec<Card>>());
+
+        Ok(())
     }
 }
 
-impl Game for War {
-    fn round(&mut self) {
-        header_start!();
+impl<U: UserInterface> Game for War<U> {
+    fn round(&mut self) -> Result<RoundOutcome, CardsError> {
+        self.ui.clear();
+
+        let mut player_draws = self.draw()?;
+
+        if self.players.len() == 1 {
+            // nobody is left to play against, so the winner keeps their card
+            for draw in player_draws {
+                self.players[0].deck.insert(0, draw.card);
+            }
+
+            self.ui.pause()?;
+            self.stats.ending = Some(WarEnding::Finished);
 
-        let mut player_draws = self.draw();
+            return Ok(RoundOutcome::GameOver {
+                winners: vec![self.players[0].id],
+                standings: self.standings(),
+            });
+        }
 
-        player_draws.sort_by(|a, b| b.card.value_id.cmp(&a.card.value_id));
+        self.stats.rounds += 1;
+        player_draws.sort_by_key(|x| std::cmp::Reverse(x.card.rank));
 
-        let highest = player_draws[0].card.value_id;
+        let highest = player_draws[0].card.rank;
         let mut winners = Vec::new();
         let mut floor = Vec::new();
 
         for player_draw in player_draws.iter() {
-            if player_draw.card.value_id == highest {
+            if player_draw.card.rank == highest {
                 winners.push(player_draw.player_id);
             }
         }
 
-        floor.extend(player_draws.drain(..).collect::<Vec<PlayerCard>>());
+        floor.append(&mut player_draws);
+        self.ui.show_table(&self.table(&floor));
+
+        while winners.len() > 1 && !self.out_of_cards(&winners) {
+            self.war(&mut winners, &mut floor)?;
+            self.ui.show_table(&self.table(&floor));
+        }
 
-        while winners.len() > 1 {
-            self.war(&mut winners, &mut floor);
+        if winners.len() > 1 {
+            self.share_floor(&winners, &mut floor)?;
+        } else {
+            self.round_win(winners[0], &mut floor)?;
         }
 
-        self.round_win(winners[0], &mut floor);
+        self.ui.show_table(&self.table(&floor));
 
+        // the decks are public, so cards may have been added or taken away
         let sum: usize = self.players.iter().map(|x| x.deck.len()).sum();
-        assert_eq!(sum, 52);
 
-        header_end!();
-        prompt!("Press enter to continue...");
+        if sum != 52 {
+            return Err(CardsError::InvalidArgument(format!(
+                "the players hold {} cards instead of 52",
+                sum
+            )));
+        }
+
+        if !self.seen.insert(self.state_hash()) {
+            return self.tie_break(WarEnding::Loop);
+        }
+
+        if self.max_rounds.is_some_and(|x| self.stats.rounds >= x) {
+            return self.tie_break(WarEnding::RoundLimit);
+        }
+
+        self.ui.pause()?;
+
+        Ok(RoundOutcome::Continue)
     }
 
     fn get_players(&self) -> &Vec<Player> {
         &self.players
     }
+
+    fn standings(&self) -> Vec<Standing> {
+        let mut standings = self
+            .players
+            .iter()
+            .map(|x| Standing {
+                player_id: x.id,
+                name: x.name.clone(),
+                score: x.deck.len() as i32,
+                out: false,
+            })
+            .collect::<Vec<Standing>>();
+
+        standings.sort_by_key(|x| std::cmp::Reverse(x.score));
+
+        // the last player knocked out comes first
+        standings.extend(self.eliminated.iter().rev().map(|x| Standing {
+            player_id: x.id,
+            name: x.name.clone(),
+            score: 0,
+            out: true,
+        }));
+
+        standings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::ui::ScriptedUi;
+
+    fn game() -> War<ScriptedUi> {
+        let names = vec!["Ann".to_string(), "Bob".to_string()];
+
+        War::new(names, &mut StdRng::seed_from_u64(1), ScriptedUi::default()).unwrap()
+    }
+
+    fn decks(game: &War<ScriptedUi>) -> Vec<Vec<Card>> {
+        game.players.iter().map(|x| x.deck.clone()).collect()
+    }
+
+    #[test]
+    fn ends_a_game_that_repeats() {
+        let mut game = game();
+
+        assert!(matches!(game.round(), Ok(RoundOutcome::Continue)));
+        let snapshot = decks(&game);
+        assert!(matches!(game.round(), Ok(RoundOutcome::Continue)));
+
+        // the same cards in the same order play the same round again
+        for (player, deck) in game.players.iter_mut().zip(snapshot) {
+            player.deck = deck;
+        }
+
+        assert!(matches!(game.round(), Ok(RoundOutcome::GameOver { .. })));
+        assert_eq!(game.stats.ending, Some(WarEnding::Loop));
+    }
+
+    #[test]
+    fn ends_at_the_round_limit() {
+        let mut game = game();
+        game.max_rounds = Some(1);
+
+        assert!(matches!(game.round(), Ok(RoundOutcome::GameOver { .. })));
+        assert_eq!(game.stats.ending, Some(WarEnding::RoundLimit));
+        assert_eq!(game.stats.rounds, 1);
+    }
+
+    #[test]
+    fn ends_when_one_player_has_every_card() {
+        let mut game = game();
+        let cards = std::mem::take(&mut game.players[1].deck);
+        game.players[0].deck.extend(cards);
+
+        match game.round() {
+            Ok(RoundOutcome::GameOver { winners, .. }) => assert_eq!(winners, vec![0]),
+            _ => panic!("the game should be over"),
+        }
+        assert_eq!(game.stats.ending, Some(WarEnding::Finished));
+        assert_eq!(game.players[0].deck.len(), 52);
+        assert_eq!(game.eliminated.len(), 1);
+    }
+
+    #[test]
+    fn reports_lost_cards() {
+        let mut game = game();
+        game.players[0].deck.pop();
+
+        assert!(matches!(game.round(), Err(CardsError::InvalidArgument(_))));
+    }
 }